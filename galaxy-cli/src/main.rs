@@ -0,0 +1,352 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::Args;
+use clap::Parser;
+use clap::Subcommand;
+use galaxy_core::game_state::GameState;
+use galaxy_core::init::GameConfig;
+use galaxy_core::init::initialize_game;
+use galaxy_core::race::RaceId;
+use galaxy_core::save::SaveFormat;
+
+/// Headless GALAXY simulation runner
+#[derive(Debug, Parser)]
+#[command(name = "galaxy-cli", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Create a new game and save it
+    New {
+        /// JSON game config (missing fields use defaults)
+        #[arg(long)]
+        config: Option<PathBuf>,
+        /// Seed overriding the one in the config
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Game state file to create (`.json` for JSON, anything else binary)
+        #[arg(long)]
+        state: PathBuf,
+        /// Also write a summary of the new game to this file
+        #[arg(long)]
+        summary: Option<PathBuf>,
+    },
+    /// Advance a saved game up to N turns, stopping early on victory
+    Run {
+        /// Game state file to advance
+        #[arg(long)]
+        state: PathBuf,
+        /// Maximum number of turns to play
+        #[arg(long)]
+        turns: u32,
+        #[command(flatten)]
+        output: Output,
+    },
+    /// Advance a saved game by a single turn
+    Step {
+        /// Game state file to advance
+        #[arg(long)]
+        state: PathBuf,
+        #[command(flatten)]
+        output: Output,
+    },
+    /// Apply a human player's orders file to a saved game
+    Orders {
+        /// Game state file to update
+        #[arg(long)]
+        state: PathBuf,
+        /// Race giving the orders
+        #[arg(long)]
+        race: u32,
+        /// Orders file in the GalaxyNG-style orders language
+        #[arg(long)]
+        orders: PathBuf,
+        /// Write the updated state here instead of overwriting the input
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Summarize a saved game, or print one race's turn report
+    Report {
+        /// Game state file to summarize
+        #[arg(long)]
+        state: PathBuf,
+        /// Write the summary here instead of stdout
+        #[arg(long)]
+        summary: Option<PathBuf>,
+        /// Print the turn report of this race instead of the summary
+        #[arg(long)]
+        race: Option<u32>,
+        /// Print the race report as JSON
+        #[arg(long, requires = "race")]
+        json: bool,
+    },
+}
+
+/// Where an advanced game and its summary go
+#[derive(Debug, Args)]
+struct Output {
+    /// Write the advanced state here instead of overwriting the input
+    #[arg(long)]
+    out: Option<PathBuf>,
+    /// Also write a summary of the game to this file
+    #[arg(long)]
+    summary: Option<PathBuf>,
+    /// Write every race's turn report (`race-N.txt` and `race-N.json`) to
+    /// this directory
+    #[arg(long)]
+    reports: Option<PathBuf>,
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    match cli.command {
+        Command::New {
+            config,
+            seed,
+            state,
+            summary,
+        } => {
+            let mut config = match config {
+                Some(path) => serde_json::from_slice(&fs::read(path)?)?,
+                None => GameConfig::default(),
+            };
+            if let Some(seed) = seed {
+                config.seed = seed;
+            }
+
+            let game = initialize_game(config);
+            save(&game, &state)?;
+            if let Some(path) = summary {
+                fs::write(path, summarize(&game))?;
+            }
+        }
+        Command::Run {
+            state,
+            turns,
+            output,
+        } => {
+            let mut game = GameState::load(&state)?;
+            if let Some(winner) = game.run_simulation(turns) {
+                let name = game.get_race(winner).map_or("?", |r| r.name());
+                println!("{name} won on turn {}", game.turn());
+            }
+            finish(&game, &state, output)?;
+        }
+        Command::Step { state, output } => {
+            let mut game = GameState::load(&state)?;
+            println!("Turn {}", game.turn() + 1);
+            for event in game.advance_turn() {
+                println!("  {event}");
+            }
+            finish(&game, &state, output)?;
+        }
+        Command::Orders {
+            state,
+            race,
+            orders,
+            out,
+        } => {
+            let mut game = GameState::load(&state)?;
+            let result = game.apply_orders(RaceId(race), &fs::read_to_string(orders)?);
+            // Valid orders are kept even when some lines were rejected
+            save(&game, out.as_deref().unwrap_or(&state))?;
+            if let Err(errors) = result {
+                for error in &errors {
+                    eprintln!("{error}");
+                }
+                return Err(format!("{} orders rejected", errors.len()).into());
+            }
+        }
+        Command::Report {
+            state,
+            summary,
+            race,
+            json,
+        } => {
+            let game = GameState::load(&state)?;
+            if let Some(race) = race {
+                let report = game
+                    .race_report(RaceId(race))
+                    .ok_or_else(|| format!("no race {race} in this game"))?;
+                if json {
+                    println!("{}", report.to_json()?);
+                } else {
+                    print!("{report}");
+                }
+            } else {
+                match summary {
+                    Some(path) => fs::write(path, summarize(&game))?,
+                    None => print!("{}", summarize(&game)),
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Save an advanced game and write its summary and reports if requested
+fn finish(game: &GameState, input: &Path, output: Output) -> Result<(), Box<dyn Error>> {
+    save(game, output.out.as_deref().unwrap_or(input))?;
+    if let Some(path) = output.summary {
+        fs::write(path, summarize(game))?;
+    }
+    if let Some(dir) = output.reports {
+        fs::create_dir_all(&dir)?;
+        for race in game.races() {
+            let Some(report) = game.race_report(race.id()) else {
+                continue;
+            };
+            let id = race.id().0;
+            fs::write(dir.join(format!("race-{id}.txt")), report.to_string())?;
+            fs::write(dir.join(format!("race-{id}.json")), report.to_json()?)?;
+        }
+    }
+    Ok(())
+}
+
+fn save(game: &GameState, path: &Path) -> Result<(), Box<dyn Error>> {
+    game.save(path, SaveFormat::from_path(path))?;
+    Ok(())
+}
+
+/// One-page overview of the galaxy and every race in it
+fn summarize(game: &GameState) -> String {
+    let galaxy = game.galaxy();
+    let owned_planets = galaxy.planets().filter(|p| p.owner().is_some()).count();
+
+    let mut lines = vec![
+        format!("Turn {}", game.turn()),
+        format!(
+            "Galaxy {}x{}: {} planets ({} owned), {} ships",
+            galaxy.width(),
+            galaxy.height(),
+            galaxy.planets().count(),
+            owned_planets,
+            game.ships().count()
+        ),
+        String::new(),
+        format!(
+            "{:<16} {:>7} {:>10} {:>10} {:>10} {:>5}  {}",
+            "Race", "Planets", "Population", "Industry", "Materials", "Ships", "Tech D/W/S/C"
+        ),
+    ];
+
+    for race in game.races() {
+        let planets: Vec<_> = galaxy.planets_owned_by(race.id().0).collect();
+        let tech = race.technology();
+        lines.push(format!(
+            "{:<16} {:>7} {:>10.1} {:>10.1} {:>10.1} {:>5}  {}/{}/{}/{}",
+            race.name(),
+            planets.len(),
+            planets.iter().map(|p| p.population()).sum::<f64>(),
+            planets.iter().map(|p| p.industry()).sum::<f64>(),
+            planets.iter().map(|p| p.materials()).sum::<f64>(),
+            game.ships().filter(|s| s.owner() == race.id()).count(),
+            tech.drive_level(),
+            tech.weapon_level(),
+            tech.shield_level(),
+            tech.cargo_level()
+        ));
+    }
+
+    if let Some(leader) = game.check_victory().and_then(|id| game.get_race(id)) {
+        lines.push(String::new());
+        lines.push(format!("Leader: {}", leader.name()));
+    }
+
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run one command line through the same handlers as `main`
+    fn galaxy_cli(args: &[&str]) -> Result<(), Box<dyn Error>> {
+        run(Cli::try_parse_from(
+            ["galaxy-cli"].iter().chain(args).copied(),
+        )?)
+    }
+
+    #[test]
+    fn test_new_orders_step_report() {
+        let dir = std::env::temp_dir().join(format!("galaxy-cli-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+
+        galaxy_cli(&["new", "--seed", "5", "--state", &path("game.json")]).unwrap();
+
+        fs::write(path("orders.txt"), "d Probe 1 0 0 0 0 ; a scout\n").unwrap();
+        galaxy_cli(&[
+            "orders",
+            "--state",
+            &path("game.json"),
+            "--race",
+            "0",
+            "--orders",
+            &path("orders.txt"),
+        ])
+        .unwrap();
+
+        galaxy_cli(&[
+            "step",
+            "--state",
+            &path("game.json"),
+            "--out",
+            &path("game.sav"),
+            "--reports",
+            &path("reports"),
+        ])
+        .unwrap();
+        galaxy_cli(&[
+            "report",
+            "--state",
+            &path("game.sav"),
+            "--summary",
+            &path("summary.txt"),
+        ])
+        .unwrap();
+
+        let game = GameState::load(path("game.sav")).unwrap();
+        assert_eq!(game.turn(), 1);
+        let report = fs::read_to_string(path("reports/race-0.txt")).unwrap();
+        assert!(report.contains("Probe"));
+        let summary = fs::read_to_string(path("summary.txt")).unwrap();
+        assert!(summary.starts_with("Turn 1\n"));
+
+        // Rejected orders are reported as an error
+        fs::write(path("orders.txt"), "s 9999 0\n").unwrap();
+        assert!(
+            galaxy_cli(&[
+                "orders",
+                "--state",
+                &path("game.sav"),
+                "--race",
+                "0",
+                "--orders",
+                &path("orders.txt"),
+            ])
+            .is_err()
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
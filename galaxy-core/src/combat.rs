@@ -1,9 +1,19 @@
-use bevy::prelude::*;
+#[cfg(feature = "bevy")]
+use bevy::prelude::Resource;
 use rand::Rng;
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+use serde::Serialize;
 
+use crate::planet::PlanetId;
 use crate::race::RaceId;
 use crate::race::Technology;
 use crate::ship::Ship;
+use crate::ship::ShipDesign;
+use crate::ship::ShipId;
+
+/// Battles end after this many rounds even if hostile ships remain
+const MAX_BATTLE_ROUNDS: u32 = 100;
 
 /// Result of a combat encounter
 #[derive(Debug, Clone)]
@@ -15,8 +25,81 @@ pub struct CombatResult {
     pub rounds: u32,
 }
 
+/// Something that fights in a battle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Combatant {
+    Ship(ShipId),
+    /// The defences of the planet the battle is fought at
+    Planet(PlanetId),
+}
+
+/// The guns and shields of a planet, fighting for its owner
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanetDefences {
+    pub owner: RaceId,
+    pub design: ShipDesign,
+    /// The owner's tech levels
+    pub tech: Technology,
+}
+
+/// A single shot fired in a battle
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Shot {
+    pub round: u32,
+    pub attacker: Combatant,
+    pub target: Combatant,
+    pub destroyed: bool,
+}
+
+/// Everything that happened in a battle at a planet
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BattleReport {
+    pub planet: PlanetId,
+    /// Races with ships or defences in the battle
+    pub races: Vec<RaceId>,
+    pub rounds: u32,
+    pub shots: Vec<Shot>,
+    pub ships_lost: Vec<ShipId>,
+    /// Whether the planet's defences were knocked out
+    pub defences_destroyed: bool,
+}
+
+/// A combatant's part in a battle, worked out before the first shot
+struct Fighter {
+    id: Combatant,
+    owner: RaceId,
+    attacks: u32,
+    attack: f64,
+    defence: f64,
+}
+
+impl Fighter {
+    fn ship(ship: &Ship) -> Self {
+        Self {
+            id: Combatant::Ship(ship.id()),
+            owner: ship.owner(),
+            attacks: ship.design().attacks(),
+            attack: ship.attack_strength(),
+            defence: ship.defence_strength(),
+        }
+    }
+
+    fn planet(planet: PlanetId, defences: &PlanetDefences) -> Self {
+        let design = &defences.design;
+        let tech = &defences.tech;
+        Self {
+            id: Combatant::Planet(planet),
+            owner: defences.owner,
+            attacks: design.attacks(),
+            attack: design.attack_strength(tech.weapon_level() as f64),
+            defence: design.defence_strength(tech.shield_level() as f64, 0.0),
+        }
+    }
+}
+
 /// Combat system for ship-to-ship battles
-#[derive(Debug, Default, Resource)]
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "bevy", derive(Resource))]
 pub struct CombatSystem;
 
 impl CombatSystem {
@@ -26,63 +109,162 @@ impl CombatSystem {
 
     /// Resolve combat between two ships using GalaxyNG probabilistic formulas
     ///
-    /// Kill probability: p[kill] = (log4(attack/defence) + 1) / 2
-    /// Combat continues until one or both ships are destroyed
+    /// A battle between just these two ships; see [`Self::resolve_battle`].
     pub fn resolve_combat(
         attacker: &mut Ship,
-        attacker_tech: &Technology,
         defender: &mut Ship,
-        defender_tech: &Technology,
+        rng: &mut impl Rng,
     ) -> CombatResult {
-        let mut rounds = 0;
-        let mut attacker_total_damage = 0.0;
-        let mut defender_total_damage = 0.0;
-        let mut rng = rand::thread_rng();
-
-        while !attacker.is_destroyed() && !defender.is_destroyed() {
-            rounds += 1;
-
-            // Calculate attack and defence strengths
-            let attacker_attack = attacker.attack_strength(attacker_tech.weapon_level() as f64);
-            let attacker_defence = attacker.defence_strength(attacker_tech.shield_level() as f64);
-
-            let defender_attack = defender.attack_strength(defender_tech.weapon_level() as f64);
-            let defender_defence = defender.defence_strength(defender_tech.shield_level() as f64);
-
-            // Attacker tries to kill defender
-            if attacker_attack > 0.0 && defender_defence > 0.0 {
-                let p_attacker_kills =
-                    Self::calculate_kill_probability(attacker_attack, defender_defence);
-                if rng.gen_range(0.0..1.0) < p_attacker_kills {
-                    let damage = defender.current_hull();
-                    defender.take_damage(damage);
-                    attacker_total_damage += damage;
-                }
+        let hulls = [attacker.current_hull(), defender.current_hull()];
+        let ids = [attacker.id(), defender.id()].map(Combatant::Ship);
+        let (rounds, shots) = Self::fight(&mut [attacker, defender], None, |_, _| true, rng);
+
+        let lost = |ship: Combatant| shots.iter().any(|s| s.target == ship && s.destroyed);
+        let damage_dealt = |by: Combatant, hull: f64| {
+            if shots.iter().any(|s| s.attacker == by && s.destroyed) {
+                hull
+            } else {
+                0.0
             }
+        };
+        CombatResult {
+            attacker_survived: !lost(ids[0]),
+            defender_survived: !lost(ids[1]),
+            attacker_damage_dealt: damage_dealt(ids[0], hulls[1]),
+            defender_damage_dealt: damage_dealt(ids[1], hulls[0]),
+            rounds,
+        }
+    }
 
-            // Defender tries to kill attacker (if still alive)
-            if !defender.is_destroyed() && defender_attack > 0.0 && attacker_defence > 0.0 {
-                let p_defender_kills =
-                    Self::calculate_kill_probability(defender_attack, attacker_defence);
-                if rng.gen_range(0.0..1.0) < p_defender_kills {
-                    let damage = attacker.current_hull();
-                    attacker.take_damage(damage);
-                    defender_total_damage += damage;
+    /// Resolve a GalaxyNG battle between all ships at a planet
+    ///
+    /// Every round the armed ships fire in random order, one shot per attack
+    /// of their design, each at a random ship of a race they are `hostile` to,
+    /// with p[kill] = (log4(attack / defence) + 1) / 2; unshielded ships die
+    /// to any hit. Destroyed ships drop out at once. The battle goes on until
+    /// no ship can hurt a hostile one any more. Ships fight with the tech
+    /// levels they were built or upgraded with. The planet's `defences`, if
+    /// any, fight for its owner like one more ship.
+    pub fn resolve_battle(
+        planet: PlanetId,
+        ships: &mut [&mut Ship],
+        defences: Option<&PlanetDefences>,
+        hostile: impl Fn(RaceId, RaceId) -> bool,
+        rng: &mut impl Rng,
+    ) -> BattleReport {
+        let owners: Vec<RaceId> = ships
+            .iter()
+            .map(|ship| ship.owner())
+            .chain(defences.map(|defences| defences.owner))
+            .collect();
+        let mut races: Vec<RaceId> = owners
+            .iter()
+            .copied()
+            .filter(|race| owners.iter().any(|other| hostile(*race, *other)))
+            .collect();
+        races.sort();
+        races.dedup();
+
+        let defences = defences.map(|defences| Fighter::planet(planet, defences));
+        let (rounds, shots) = Self::fight(ships, defences, &hostile, rng);
+        let destroyed = shots.iter().filter(|shot| shot.destroyed);
+        let ships_lost = destroyed
+            .clone()
+            .filter_map(|shot| match shot.target {
+                Combatant::Ship(ship) => Some(ship),
+                Combatant::Planet(_) => None,
+            })
+            .collect();
+        let defences_destroyed = destroyed
+            .clone()
+            .any(|shot| matches!(shot.target, Combatant::Planet(_)));
+        BattleReport {
+            planet,
+            races,
+            rounds,
+            shots,
+            ships_lost,
+            defences_destroyed,
+        }
+    }
+
+    /// Fight rounds until no combatant can hurt a hostile one; destroyed ships
+    /// are left with no hull
+    fn fight(
+        ships: &mut [&mut Ship],
+        defences: Option<Fighter>,
+        hostile: impl Fn(RaceId, RaceId) -> bool,
+        rng: &mut impl Rng,
+    ) -> (u32, Vec<Shot>) {
+        let fighters: Vec<Fighter> = ships
+            .iter()
+            .map(|ship| Fighter::ship(ship))
+            .chain(defences)
+            .collect();
+        let count = fighters.len();
+        let mut alive = vec![true; count];
+        let mut shots = Vec::new();
+        let mut round = 0;
+
+        let enemies = |alive: &[bool], a: usize| -> Vec<usize> {
+            (0..count)
+                .filter(|&b| b != a && alive[b] && hostile(fighters[a].owner, fighters[b].owner))
+                .collect()
+        };
+        let can_fight = |alive: &[bool]| {
+            (0..count).filter(|&a| alive[a]).any(|a| {
+                enemies(alive, a)
+                    .into_iter()
+                    .any(|b| Self::shot_kill_probability(&fighters[a], &fighters[b]) > 0.0)
+            })
+        };
+
+        while round < MAX_BATTLE_ROUNDS && can_fight(&alive) {
+            round += 1;
+            let mut order: Vec<usize> = (0..count)
+                .filter(|&a| alive[a] && fighters[a].attacks > 0)
+                .collect();
+            order.shuffle(rng);
+
+            for a in order {
+                for _ in 0..fighters[a].attacks {
+                    // Combatants destroyed earlier in the round do not get to fire
+                    if !alive[a] {
+                        break;
+                    }
+                    let Some(&b) = enemies(&alive, a).choose(rng) else {
+                        break;
+                    };
+                    let p_kill = Self::shot_kill_probability(&fighters[a], &fighters[b]);
+                    let destroyed = rng.gen_range(0.0..1.0) < p_kill;
+                    if destroyed {
+                        alive[b] = false;
+                    }
+                    shots.push(Shot {
+                        round,
+                        attacker: fighters[a].id,
+                        target: fighters[b].id,
+                        destroyed,
+                    });
                 }
             }
+        }
 
-            // Prevent infinite loops - max 100 rounds
-            if rounds >= 100 {
-                break;
+        for (ship, alive) in ships.iter_mut().zip(alive) {
+            if !alive {
+                let hull = ship.current_hull();
+                ship.take_damage(hull);
             }
         }
+        (round, shots)
+    }
 
-        CombatResult {
-            attacker_survived: !attacker.is_destroyed(),
-            defender_survived: !defender.is_destroyed(),
-            attacker_damage_dealt: attacker_total_damage,
-            defender_damage_dealt: defender_total_damage,
-            rounds,
+    /// Chance that a shot of `attacker` destroys `target`
+    fn shot_kill_probability(attacker: &Fighter, target: &Fighter) -> f64 {
+        if attacker.attack > 0.0 && target.defence <= 0.0 {
+            1.0
+        } else {
+            Self::calculate_kill_probability(attacker.attack, target.defence)
         }
     }
 
@@ -112,11 +294,15 @@ impl CombatSystem {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeSet;
+
+    use rand::SeedableRng;
+
     use super::*;
-    use crate::planet::PlanetId;
+    use crate::game_state::GameRng;
     use crate::race::Technology;
+    use crate::ship::CargoType;
     use crate::ship::ShipDesign;
-    use crate::ship::ShipId;
 
     #[test]
     fn test_kill_probability_formula() {
@@ -147,7 +333,7 @@ mod tests {
         let design1 = ShipDesign::new(1.0, 1, 3.0, 2.0, 0.0);
         let design2 = ShipDesign::new(1.0, 1, 3.0, 2.0, 0.0);
 
-        let tech = Technology::new();
+        let mut rng = GameRng::seed_from_u64(7);
 
         let mut attacker_wins = 0;
         let mut defender_wins = 0;
@@ -158,7 +344,7 @@ mod tests {
             let mut ship1 = Ship::new(ShipId(0), RaceId(0), design1, PlanetId(0));
             let mut ship2 = Ship::new(ShipId(1), RaceId(1), design2, PlanetId(1));
 
-            let result = CombatSystem::resolve_combat(&mut ship1, &tech, &mut ship2, &tech);
+            let result = CombatSystem::resolve_combat(&mut ship1, &mut ship2, &mut rng);
 
             if result.attacker_survived && !result.defender_survived {
                 attacker_wins += 1;
@@ -195,7 +381,7 @@ mod tests {
         let strong_design = ShipDesign::new(2.0, 1, 10.0, 20.0, 0.0);
         let weak_design = ShipDesign::new(1.0, 1, 0.5, 1.0, 0.0);
 
-        let tech = Technology::new();
+        let mut rng = GameRng::seed_from_u64(7);
 
         let mut strong_wins = 0;
 
@@ -203,7 +389,7 @@ mod tests {
             let mut strong = Ship::new(ShipId(0), RaceId(0), strong_design, PlanetId(0));
             let mut weak = Ship::new(ShipId(1), RaceId(1), weak_design, PlanetId(1));
 
-            let result = CombatSystem::resolve_combat(&mut strong, &tech, &mut weak, &tech);
+            let result = CombatSystem::resolve_combat(&mut strong, &mut weak, &mut rng);
 
             if result.attacker_survived && !result.defender_survived {
                 strong_wins += 1;
@@ -223,23 +409,32 @@ mod tests {
         tech_high.advance(crate::race::TechnologyType::Weapon);
 
         let tech_low = Technology::new();
+        let mut rng = GameRng::seed_from_u64(7);
 
         let mut high_tech_wins = 0;
+        let mut low_tech_wins = 0;
 
-        for _ in 0..20 {
-            let mut ship1 = Ship::new(ShipId(0), RaceId(0), design, PlanetId(0));
-            let mut ship2 = Ship::new(ShipId(1), RaceId(1), design, PlanetId(1));
+        for _ in 0..100 {
+            let mut ship1 =
+                Ship::new(ShipId(0), RaceId(0), design, PlanetId(0)).with_tech(tech_high.clone());
+            let mut ship2 =
+                Ship::new(ShipId(1), RaceId(1), design, PlanetId(1)).with_tech(tech_low.clone());
 
-            let result =
-                CombatSystem::resolve_combat(&mut ship1, &tech_high, &mut ship2, &tech_low);
+            let result = CombatSystem::resolve_combat(&mut ship1, &mut ship2, &mut rng);
 
             if result.attacker_survived && !result.defender_survived {
                 high_tech_wins += 1;
+            } else if !result.attacker_survived && result.defender_survived {
+                low_tech_wins += 1;
             }
         }
 
-        // Higher tech should win most of the time
-        assert!(high_tech_wins >= 15, "Higher tech should win most combats");
+        // Neither side shoots first, so higher tech wins about three times in
+        // four
+        assert!(
+            high_tech_wins >= 60,
+            "Higher tech should win most combats: {high_tech_wins} to {low_tech_wins}"
+        );
     }
 
     #[test]
@@ -247,17 +442,175 @@ mod tests {
         // Create ships that can't kill each other easily
         let design = ShipDesign::new(1.0, 1, 0.1, 100.0, 0.0);
 
-        let tech = Technology::new();
+        let mut rng = GameRng::seed_from_u64(7);
 
         let mut ship1 = Ship::new(ShipId(0), RaceId(0), design, PlanetId(0));
         let mut ship2 = Ship::new(ShipId(1), RaceId(1), design, PlanetId(1));
 
-        let result = CombatSystem::resolve_combat(&mut ship1, &tech, &mut ship2, &tech);
+        let result = CombatSystem::resolve_combat(&mut ship1, &mut ship2, &mut rng);
 
         // Should terminate even if no one dies
         assert!(result.rounds <= 100, "Combat should not exceed 100 rounds");
     }
 
+    #[test]
+    fn test_combat_reproducible_with_seed() {
+        let design = ShipDesign::new(1.0, 1, 3.0, 2.0, 0.0);
+
+        let run = |seed| {
+            let mut rng = GameRng::seed_from_u64(seed);
+            (0..20)
+                .map(|_| {
+                    let mut ship1 = Ship::new(ShipId(0), RaceId(0), design, PlanetId(0));
+                    let mut ship2 = Ship::new(ShipId(1), RaceId(1), design, PlanetId(1));
+                    let result = CombatSystem::resolve_combat(&mut ship1, &mut ship2, &mut rng);
+                    (
+                        result.attacker_survived,
+                        result.defender_survived,
+                        result.rounds,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Same seed must replay the exact same sequence of battles
+        assert_eq!(run(42), run(42));
+    }
+
+    #[test]
+    fn test_battle_between_three_races() {
+        let warship = ShipDesign::new(1.0, 1, 4.0, 4.0, 0.0);
+        let freighter = ShipDesign::new(1.0, 0, 0.0, 1.0, 1.0);
+        let mut ships = [
+            Ship::new(ShipId(0), RaceId(0), warship, PlanetId(0)),
+            Ship::new(ShipId(1), RaceId(0), warship, PlanetId(0)),
+            Ship::new(ShipId(2), RaceId(1), warship, PlanetId(0)),
+            Ship::new(ShipId(3), RaceId(2), freighter, PlanetId(0)),
+            // At peace with everyone, so nobody shoots at it
+            Ship::new(ShipId(4), RaceId(3), freighter, PlanetId(0)),
+        ];
+        let hostile = |a: RaceId, b: RaceId| a != b && a != RaceId(3) && b != RaceId(3);
+        let mut rng = GameRng::seed_from_u64(7);
+
+        let mut refs: Vec<&mut Ship> = ships.iter_mut().collect();
+        let report = CombatSystem::resolve_battle(PlanetId(0), &mut refs, None, hostile, &mut rng);
+
+        assert_eq!(report.planet, PlanetId(0));
+        assert_eq!(report.races, vec![RaceId(0), RaceId(1), RaceId(2)]);
+        assert!(report.rounds > 0);
+        // Only armed ships fire, never at the bystander
+        for shot in &report.shots {
+            assert!(matches!(shot.attacker, Combatant::Ship(ShipId(0..=2))));
+            assert_ne!(shot.target, Combatant::Ship(ShipId(4)));
+            assert!(shot.round >= 1 && shot.round <= report.rounds);
+        }
+        let lost: Vec<Combatant> = report
+            .shots
+            .iter()
+            .filter(|s| s.destroyed)
+            .map(|s| s.target)
+            .collect();
+        let ships_lost: Vec<Combatant> = report
+            .ships_lost
+            .iter()
+            .copied()
+            .map(Combatant::Ship)
+            .collect();
+        assert_eq!(ships_lost, lost);
+        assert!(!report.defences_destroyed);
+        // The battle ends once only one of the warring races is left
+        let survivors: BTreeSet<RaceId> = ships
+            .iter()
+            .filter(|s| !report.ships_lost.contains(&s.id()) && s.owner() != RaceId(3))
+            .map(|s| s.owner())
+            .collect();
+        assert!(survivors.len() <= 1);
+        assert!(!report.ships_lost.contains(&ShipId(4)));
+    }
+
+    #[test]
+    fn test_every_attack_fires_each_round() {
+        let gunboat = ShipDesign::new(1.0, 3, 1.0, 1.0, 0.0);
+        // Hard to kill: 1 attack against 3.43 defence gives p[kill] ≈ 0.05
+        let hulk = ShipDesign::new(1.0, 0, 0.0, 1.5, 0.0);
+        let mut ships = vec![Ship::new(ShipId(0), RaceId(0), gunboat, PlanetId(0))];
+        ships.extend((1..4).map(|i| Ship::new(ShipId(i), RaceId(1), hulk, PlanetId(0))));
+        let mut refs: Vec<&mut Ship> = ships.iter_mut().collect();
+        let mut rng = GameRng::seed_from_u64(7);
+
+        let report =
+            CombatSystem::resolve_battle(PlanetId(0), &mut refs, None, |a, b| a != b, &mut rng);
+
+        let first_round: Vec<&Shot> = report.shots.iter().filter(|s| s.round == 1).collect();
+        assert_eq!(first_round.len(), 3);
+        assert!(
+            first_round
+                .iter()
+                .all(|s| s.attacker == Combatant::Ship(ShipId(0)))
+        );
+        // Every round until the last, all three guns fire
+        for round in 1..report.rounds {
+            let fired = report.shots.iter().filter(|s| s.round == round).count();
+            assert_eq!(fired, 3);
+        }
+    }
+
+    #[test]
+    fn test_battle_reproducible_with_seed() {
+        let design = ShipDesign::new(1.0, 1, 3.0, 2.0, 0.0);
+        let run = |seed| {
+            let mut ships: Vec<Ship> = (0..6)
+                .map(|i| Ship::new(ShipId(i), RaceId(i % 3), design, PlanetId(0)))
+                .collect();
+            let mut refs: Vec<&mut Ship> = ships.iter_mut().collect();
+            let mut rng = GameRng::seed_from_u64(seed);
+            CombatSystem::resolve_battle(PlanetId(0), &mut refs, None, |a, b| a != b, &mut rng)
+        };
+
+        assert_eq!(run(42), run(42));
+    }
+
+    #[test]
+    fn test_loaded_ships_are_easier_to_kill() {
+        let gunship = ShipDesign::new(5.0, 1, 5.0, 5.0, 0.0);
+        let freighter = ShipDesign::new(5.0, 1, 5.0, 5.0, 10.0);
+        let attacker = Ship::new(ShipId(0), RaceId(0), gunship, PlanetId(0));
+        let mut target = Ship::new(ShipId(1), RaceId(1), freighter, PlanetId(0));
+
+        let kill_probability = |target: &Ship| {
+            CombatSystem::shot_kill_probability(&Fighter::ship(&attacker), &Fighter::ship(target))
+        };
+        let empty = kill_probability(&target);
+        target.load_cargo(CargoType::Materials, 20.0, 1.0).unwrap();
+        let loaded = kill_probability(&target);
+        assert!(loaded > empty);
+
+        // Over many duels the loaded freighter is lost more often
+        let mut rng = GameRng::seed_from_u64(23);
+        let mut losses = [0, 0];
+        for (i, cargo) in [0.0, 20.0].into_iter().enumerate() {
+            for _ in 0..200 {
+                let mut gun = attacker.clone();
+                let mut ship = Ship::new(ShipId(1), RaceId(1), freighter, PlanetId(0));
+                if cargo > 0.0 {
+                    ship.load_cargo(CargoType::Materials, cargo, 1.0).unwrap();
+                }
+                let mut ships = [&mut gun, &mut ship];
+                let report = CombatSystem::resolve_battle(
+                    PlanetId(0),
+                    &mut ships,
+                    None,
+                    |_, _| true,
+                    &mut rng,
+                );
+                if report.ships_lost.contains(&ShipId(1)) {
+                    losses[i] += 1;
+                }
+            }
+        }
+        assert!(losses[1] > losses[0]);
+    }
+
     #[test]
     fn test_should_engage_different_races() {
         assert!(CombatSystem::should_engage(RaceId(0), RaceId(1)));
@@ -1,11 +1,17 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
-use bevy::prelude::*;
+#[cfg(feature = "bevy")]
+use bevy::prelude::Component;
+#[cfg(feature = "bevy")]
+use bevy::prelude::Resource;
+use serde::Deserialize;
+use serde::Serialize;
 
 use crate::race::RaceId;
 
 /// Relationship between two races
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Component, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(Component))]
 pub enum Relationship {
     /// Races are allies - ships will not attack
     Friendly,
@@ -17,17 +23,19 @@ pub enum Relationship {
 }
 
 /// Manages diplomatic relationships between all races
-#[derive(Debug, Clone, Resource, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(Resource))]
 pub struct Diplomacy {
-    // HashMap of (race1_id, race2_id) -> Relationship
+    // BTreeMap of (race1_id, race2_id) -> Relationship
     // We store relationships as ordered pairs where race1_id < race2_id
-    relationships: HashMap<(u32, u32), Relationship>,
+    #[serde(with = "relationship_entries")]
+    relationships: BTreeMap<(u32, u32), Relationship>,
 }
 
 impl Diplomacy {
     pub fn new() -> Self {
         Self {
-            relationships: HashMap::new(),
+            relationships: BTreeMap::new(),
         }
     }
 
@@ -74,12 +82,38 @@ impl Diplomacy {
         self.are_hostile(race1, race2)
     }
 
-    // Helper to create ordered key for HashMap
+    // Helper to create ordered key for BTreeMap
     fn make_key(id1: u32, id2: u32) -> (u32, u32) {
         if id1 < id2 { (id1, id2) } else { (id2, id1) }
     }
 }
 
+/// Serializes the relationship map as a list of entries, since tuple keys
+/// cannot be used as JSON object keys
+mod relationship_entries {
+    use std::collections::BTreeMap;
+
+    use serde::Deserialize;
+    use serde::Deserializer;
+    use serde::Serializer;
+
+    use super::Relationship;
+
+    pub(super) fn serialize<S: Serializer>(
+        relationships: &BTreeMap<(u32, u32), Relationship>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(relationships.iter())
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BTreeMap<(u32, u32), Relationship>, D::Error> {
+        let entries = Vec::<((u32, u32), Relationship)>::deserialize(deserializer)?;
+        Ok(entries.into_iter().collect())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,167 @@
+use std::fmt;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::fleet::GroupId;
+use crate::planet::PlanetId;
+use crate::race::RaceId;
+use crate::ship::CargoType;
+use crate::ship::DesignError;
+use crate::ship::ShipId;
+
+/// Why the game refused an action
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GameError {
+    UnknownRace(RaceId),
+    UnknownPlanet(PlanetId),
+    UnknownShip(ShipId),
+    UnknownShipType(String),
+    /// The race already has a ship type with this name
+    DuplicateShipType(String),
+    /// Ships of this type exist or are being built
+    ShipTypeInUse(String),
+    InvalidDesign(DesignError),
+    UnknownGroup(GroupId),
+    /// The race has no fleet with this name
+    UnknownFleet(String),
+    /// The planet belongs to someone else (or nobody)
+    NotPlanetOwner {
+        planet: PlanetId,
+        race: RaceId,
+    },
+    NotShipOwner {
+        ship: ShipId,
+        race: RaceId,
+    },
+    NotGroupOwner {
+        group: GroupId,
+        race: RaceId,
+    },
+    /// A group needs at least one ship
+    EmptyGroup,
+    /// Only identical ships can form a group
+    ShipsDiffer {
+        ship: ShipId,
+        other: ShipId,
+    },
+    /// The ship is not at the planet where the rest of its group or fleet is
+    NotTogether {
+        ship: ShipId,
+        planet: PlanetId,
+    },
+    /// No ships of other races are on their way from the planet
+    NothingToIntercept(PlanetId),
+    /// A split has to leave ships in both groups
+    InvalidSplit {
+        group: GroupId,
+        count: usize,
+    },
+    /// Only owned planets can build ships
+    PlanetUnowned(PlanetId),
+    /// The ship is between planets and cannot act until it arrives
+    ShipInFlight(ShipId),
+    AlreadyAtDestination {
+        ship: ShipId,
+        planet: PlanetId,
+    },
+    /// A planet's stockpile does not cover the cost
+    InsufficientStockpile {
+        planet: PlanetId,
+        resource: CargoType,
+        needed: f64,
+        available: f64,
+    },
+    /// The planet has not got enough production left this turn
+    InsufficientProduction {
+        planet: PlanetId,
+        needed: f64,
+        available: f64,
+    },
+    /// A route has to lead to another planet
+    RouteToOrigin(PlanetId),
+    /// The ship has no free cargo space left
+    CargoHoldFull(ShipId),
+    /// Amounts must be positive and finite
+    InvalidAmount(f64),
+    /// A race cannot change its relationship with itself
+    SelfRelationship(RaceId),
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownRace(race) => write!(f, "there is no race #{}", race.0),
+            Self::UnknownPlanet(planet) => write!(f, "there is no planet #{}", planet.0),
+            Self::UnknownShip(ship) => write!(f, "there is no ship #{}", ship.0),
+            Self::UnknownShipType(name) => write!(f, "unknown ship type `{name}`"),
+            Self::DuplicateShipType(name) => write!(f, "ship type `{name}` already exists"),
+            Self::ShipTypeInUse(name) => write!(f, "ship type `{name}` is still in use"),
+            Self::InvalidDesign(err) => write!(f, "invalid ship design: {err}"),
+            Self::UnknownGroup(group) => write!(f, "there is no group #{}", group.0),
+            Self::UnknownFleet(name) => write!(f, "unknown fleet `{name}`"),
+            Self::NotPlanetOwner { planet, race } => {
+                write!(f, "planet #{} is not owned by race #{}", planet.0, race.0)
+            }
+            Self::NotShipOwner { ship, race } => {
+                write!(f, "ship #{} is not owned by race #{}", ship.0, race.0)
+            }
+            Self::NotGroupOwner { group, race } => {
+                write!(f, "group #{} is not owned by race #{}", group.0, race.0)
+            }
+            Self::EmptyGroup => write!(f, "a group needs at least one ship"),
+            Self::ShipsDiffer { ship, other } => {
+                write!(f, "ship #{} differs from ship #{}", ship.0, other.0)
+            }
+            Self::NotTogether { ship, planet } => {
+                write!(f, "ship #{} is not at planet #{}", ship.0, planet.0)
+            }
+            Self::NothingToIntercept(planet) => {
+                write!(f, "no foreign ships are leaving planet #{}", planet.0)
+            }
+            Self::InvalidSplit { group, count } => {
+                write!(f, "cannot split {count} ships off group #{}", group.0)
+            }
+            Self::PlanetUnowned(planet) => write!(f, "planet #{} has no owner", planet.0),
+            Self::ShipInFlight(ship) => write!(f, "ship #{} is in flight", ship.0),
+            Self::AlreadyAtDestination { ship, planet } => {
+                write!(f, "ship #{} is already at planet #{}", ship.0, planet.0)
+            }
+            Self::InsufficientStockpile {
+                planet,
+                resource,
+                needed,
+                available,
+            } => write!(
+                f,
+                "planet #{} needs {needed:.2} {resource:?} but has {available:.2}",
+                planet.0
+            ),
+            Self::InsufficientProduction {
+                planet,
+                needed,
+                available,
+            } => write!(
+                f,
+                "planet #{} needs {needed:.2} production but has {available:.2} left",
+                planet.0
+            ),
+            Self::RouteToOrigin(planet) => {
+                write!(f, "route from planet #{} leads back to itself", planet.0)
+            }
+            Self::CargoHoldFull(ship) => write!(f, "ship #{} has no free cargo space", ship.0),
+            Self::InvalidAmount(amount) => write!(f, "invalid amount {amount}"),
+            Self::SelfRelationship(race) => {
+                write!(f, "race #{} cannot change relations with itself", race.0)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+impl From<DesignError> for GameError {
+    fn from(err: DesignError) -> Self {
+        Self::InvalidDesign(err)
+    }
+}
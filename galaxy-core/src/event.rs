@@ -0,0 +1,157 @@
+use std::fmt;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::diplomacy::Relationship;
+use crate::error::GameError;
+use crate::planet::PlanetId;
+use crate::race::RaceId;
+use crate::race::TechnologyType;
+use crate::ship::ShipId;
+
+/// Something that happened while a turn was processed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TurnEvent {
+    ShipBuilt {
+        ship: ShipId,
+        race: RaceId,
+        planet: PlanetId,
+    },
+    ShipArrived {
+        ship: ShipId,
+        race: RaceId,
+        planet: PlanetId,
+    },
+    PlanetColonized {
+        planet: PlanetId,
+        race: RaceId,
+    },
+    /// Hostile ships fought at a planet
+    BattleFought {
+        planet: PlanetId,
+        races: Vec<RaceId>,
+        ships_lost: Vec<ShipId>,
+    },
+    /// An owned planet was bombed by ships of other races
+    PlanetBombed {
+        planet: PlanetId,
+        owner: RaceId,
+        attackers: Vec<RaceId>,
+    },
+    /// A race landed colonists on a planet bombed out of another race's
+    /// hands
+    PlanetCaptured {
+        planet: PlanetId,
+        previous_owner: RaceId,
+        new_owner: RaceId,
+    },
+    TechAdvanced {
+        race: RaceId,
+        tech: TechnologyType,
+        level: u32,
+    },
+    RelationshipChanged {
+        race1: RaceId,
+        race2: RaceId,
+        relationship: Relationship,
+    },
+    /// An order given by a race was refused
+    OrderRejected {
+        race: RaceId,
+        error: GameError,
+    },
+}
+
+impl TurnEvent {
+    /// Check whether a race took part in (or was the subject of) this event
+    pub fn involves(&self, race_id: RaceId) -> bool {
+        match self {
+            Self::ShipBuilt { race, .. }
+            | Self::ShipArrived { race, .. }
+            | Self::PlanetColonized { race, .. }
+            | Self::TechAdvanced { race, .. }
+            | Self::OrderRejected { race, .. } => *race == race_id,
+            Self::BattleFought { races, .. } => races.contains(&race_id),
+            Self::PlanetBombed {
+                owner, attackers, ..
+            } => *owner == race_id || attackers.contains(&race_id),
+            Self::PlanetCaptured {
+                previous_owner,
+                new_owner,
+                ..
+            } => *previous_owner == race_id || *new_owner == race_id,
+            Self::RelationshipChanged { race1, race2, .. } => {
+                *race1 == race_id || *race2 == race_id
+            }
+        }
+    }
+}
+
+impl fmt::Display for TurnEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ShipBuilt { ship, race, planet } => {
+                write!(f, "{race} built {ship} at {planet}")
+            }
+            Self::ShipArrived { ship, race, planet } => {
+                write!(f, "{ship} of {race} arrived at {planet}")
+            }
+            Self::PlanetColonized { planet, race } => write!(f, "{race} colonized {planet}"),
+            Self::BattleFought {
+                planet,
+                races,
+                ships_lost,
+            } => write!(
+                f,
+                "Battle at {planet} between {} races, {} ships lost",
+                races.len(),
+                ships_lost.len()
+            ),
+            Self::PlanetBombed {
+                planet,
+                owner,
+                attackers,
+            } => write!(f, "{planet} of {owner} bombed by {} races", attackers.len()),
+            Self::PlanetCaptured {
+                planet,
+                previous_owner,
+                new_owner,
+            } => write!(f, "{planet} of {previous_owner} captured by {new_owner}"),
+            Self::TechAdvanced { race, tech, level } => {
+                write!(f, "{race} advanced {tech:?} to level {level}")
+            }
+            Self::RelationshipChanged {
+                race1,
+                race2,
+                relationship,
+            } => write!(f, "{race1} and {race2} are now {relationship:?}"),
+            Self::OrderRejected { race, error } => write!(f, "Order of {race} rejected: {error}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_involves() {
+        let battle = TurnEvent::BattleFought {
+            planet: PlanetId(0),
+            races: vec![RaceId(0), RaceId(1)],
+            ships_lost: vec![],
+        };
+        assert!(battle.involves(RaceId(1)));
+        assert!(!battle.involves(RaceId(2)));
+
+        let capture = TurnEvent::PlanetCaptured {
+            planet: PlanetId(0),
+            previous_owner: RaceId(0),
+            new_owner: RaceId(1),
+        };
+        assert!(capture.involves(RaceId(0)));
+        assert!(capture.involves(RaceId(1)));
+        assert!(!capture.involves(RaceId(2)));
+    }
+}
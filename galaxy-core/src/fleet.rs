@@ -0,0 +1,1009 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::error::GameError;
+use crate::event::TurnEvent;
+use crate::game_state::GameState;
+use crate::planet::PlanetId;
+use crate::race::RaceId;
+use crate::ship::CargoType;
+use crate::ship::Ship;
+use crate::ship::ShipId;
+use crate::ship::ShipLocation;
+
+/// Unique identifier for a group of ships
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct GroupId(pub u32);
+
+impl fmt::Display for GroupId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("GroupId").field(&self.0).finish()
+    }
+}
+
+/// Unique identifier for a fleet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct FleetId(pub u32);
+
+impl fmt::Display for FleetId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FleetId").field(&self.0).finish()
+    }
+}
+
+/// Identical ships of one race handled as a unit, as GalaxyNG groups
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Group {
+    id: GroupId,
+    owner: RaceId,
+    ships: Vec<ShipId>,
+    fleet: Option<FleetId>,
+}
+
+impl Group {
+    pub fn id(&self) -> GroupId {
+        self.id
+    }
+
+    pub fn owner(&self) -> RaceId {
+        self.owner
+    }
+
+    pub fn ships(&self) -> &[ShipId] {
+        &self.ships
+    }
+
+    /// The fleet the group travels with, if any
+    pub fn fleet(&self) -> Option<FleetId> {
+        self.fleet
+    }
+}
+
+/// Named set of groups that travels at the speed of its slowest ship
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fleet {
+    id: FleetId,
+    owner: RaceId,
+    name: String,
+}
+
+impl Fleet {
+    pub fn id(&self) -> FleetId {
+        self.id
+    }
+
+    pub fn owner(&self) -> RaceId {
+        self.owner
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Intercept order waiting for the turn to run
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct Intercept {
+    race: RaceId,
+    group: GroupId,
+    planet: PlanetId,
+}
+
+/// All groups and fleets of a game
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Fleets {
+    groups: BTreeMap<GroupId, Group>,
+    fleets: BTreeMap<FleetId, Fleet>,
+    next_group_id: u32,
+    next_fleet_id: u32,
+    /// Intercept orders given this turn, resolved before ships move
+    intercepts: Vec<Intercept>,
+}
+
+impl Fleets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get_group(&self, id: GroupId) -> Option<&Group> {
+        self.groups.get(&id)
+    }
+
+    pub fn groups(&self) -> impl Iterator<Item = &Group> {
+        self.groups.values()
+    }
+
+    /// The group a ship belongs to, if any
+    pub fn group_of(&self, ship: ShipId) -> Option<&Group> {
+        self.groups
+            .values()
+            .find(|group| group.ships.contains(&ship))
+    }
+
+    pub fn get_fleet(&self, id: FleetId) -> Option<&Fleet> {
+        self.fleets.get(&id)
+    }
+
+    pub fn fleets(&self) -> impl Iterator<Item = &Fleet> {
+        self.fleets.values()
+    }
+
+    /// Look up one of a race's fleets by name
+    pub fn fleet_by_name(&self, owner: RaceId, name: &str) -> Option<&Fleet> {
+        self.fleets
+            .values()
+            .find(|fleet| fleet.owner == owner && fleet.name == name)
+    }
+
+    /// Groups belonging to a fleet
+    pub fn groups_in(&self, fleet: FleetId) -> impl Iterator<Item = &Group> {
+        self.groups
+            .values()
+            .filter(move |group| group.fleet == Some(fleet))
+    }
+
+    /// Ships of every group in a fleet
+    pub fn fleet_ships(&self, fleet: FleetId) -> Vec<ShipId> {
+        self.groups_in(fleet)
+            .flat_map(|group| group.ships.iter().copied())
+            .collect()
+    }
+
+    fn add_group(&mut self, owner: RaceId, ships: Vec<ShipId>) -> GroupId {
+        let id = GroupId(self.next_group_id);
+        self.next_group_id += 1;
+        self.groups.insert(
+            id,
+            Group {
+                id,
+                owner,
+                ships,
+                fleet: None,
+            },
+        );
+        id
+    }
+
+    fn add_fleet(&mut self, owner: RaceId, name: String) -> FleetId {
+        let id = FleetId(self.next_fleet_id);
+        self.next_fleet_id += 1;
+        self.fleets.insert(id, Fleet { id, owner, name });
+        id
+    }
+
+    /// Drop ships that no longer exist, then empty groups and fleets
+    fn retain_ships(&mut self, exists: impl Fn(ShipId) -> bool) {
+        for group in self.groups.values_mut() {
+            group.ships.retain(|ship| exists(*ship));
+        }
+        self.groups.retain(|_, group| !group.ships.is_empty());
+        let groups = &self.groups;
+        self.fleets
+            .retain(|id, _| groups.values().any(|group| group.fleet == Some(*id)));
+    }
+}
+
+/// Ships can share a group when they are of the same make and carry the same
+/// load
+fn identical(a: &Ship, b: &Ship) -> bool {
+    a.design() == b.design()
+        && a.ship_type() == b.ship_type()
+        && a.tech() == b.tech()
+        && [
+            CargoType::Colonists,
+            CargoType::Materials,
+            CargoType::Capital,
+        ]
+        .into_iter()
+        .all(|cargo| a.cargo(cargo) == b.cargo(cargo))
+}
+
+impl GameState {
+    /// Merge ships into one group
+    ///
+    /// The ships must be identical, belong to the race and wait at the same
+    /// planet. They join the group of the first ship if it has one, leaving
+    /// any other group they were in.
+    pub fn merge_ships(&mut self, race_id: RaceId, ships: &[ShipId]) -> Result<GroupId, GameError> {
+        let Some(&first) = ships.first() else {
+            return Err(GameError::EmptyGroup);
+        };
+        let target = self.fleets().group_of(first).map(|group| group.id());
+        let mut members: Vec<ShipId> = target
+            .and_then(|id| self.fleets().get_group(id))
+            .map(|group| group.ships().to_vec())
+            .unwrap_or_default();
+        for ship in ships {
+            if !members.contains(ship) {
+                members.push(*ship);
+            }
+        }
+        self.docked_together(race_id, &members)?;
+        let leader = self.get_ship(first).expect("ship checked");
+        if let Some(other) = members
+            .iter()
+            .find(|id| !identical(leader, self.get_ship(**id).expect("ship checked")))
+        {
+            return Err(GameError::ShipsDiffer {
+                ship: *other,
+                other: first,
+            });
+        }
+
+        let fleets = self.fleets_mut();
+        for group in fleets.groups.values_mut() {
+            if Some(group.id) != target {
+                group.ships.retain(|ship| !members.contains(ship));
+            }
+        }
+        let id = match target {
+            Some(id) => {
+                fleets.groups.get_mut(&id).expect("group exists").ships = members;
+                id
+            }
+            None => fleets.add_group(race_id, members),
+        };
+        self.prune_fleets();
+        Ok(id)
+    }
+
+    /// Break `count` ships off a group into a new group outside any fleet
+    pub fn split_group(
+        &mut self,
+        race_id: RaceId,
+        group_id: GroupId,
+        count: usize,
+    ) -> Result<GroupId, GameError> {
+        let group = self.own_group(race_id, group_id)?;
+        if count == 0 || count >= group.ships().len() {
+            return Err(GameError::InvalidSplit {
+                group: group_id,
+                count,
+            });
+        }
+
+        let fleets = self.fleets_mut();
+        let group = fleets.groups.get_mut(&group_id).expect("group checked");
+        let split = group.ships.split_off(group.ships.len() - count);
+        Ok(fleets.add_group(race_id, split))
+    }
+
+    /// Add a group to one of the race's fleets, creating the fleet if there
+    /// is none with this name
+    ///
+    /// A group can only join a fleet waiting at the same planet.
+    pub fn join_fleet(
+        &mut self,
+        race_id: RaceId,
+        group_id: GroupId,
+        name: &str,
+    ) -> Result<FleetId, GameError> {
+        let group = self.own_group(race_id, group_id)?;
+        let mut ships = group.ships().to_vec();
+        let fleet = self
+            .fleets()
+            .fleet_by_name(race_id, name)
+            .map(|fleet| fleet.id());
+        if let Some(fleet) = fleet {
+            ships.extend(self.fleets().fleet_ships(fleet));
+        }
+        self.docked_together(race_id, &ships)?;
+
+        let fleets = self.fleets_mut();
+        let fleet = fleet.unwrap_or_else(|| fleets.add_fleet(race_id, name.to_string()));
+        fleets
+            .groups
+            .get_mut(&group_id)
+            .expect("group checked")
+            .fleet = Some(fleet);
+        self.prune_fleets();
+        Ok(fleet)
+    }
+
+    /// Take a group out of its fleet
+    pub fn leave_fleet(&mut self, race_id: RaceId, group_id: GroupId) -> Result<(), GameError> {
+        self.own_group(race_id, group_id)?;
+        self.fleets_mut()
+            .groups
+            .get_mut(&group_id)
+            .expect("group checked")
+            .fleet = None;
+        self.prune_fleets();
+        Ok(())
+    }
+
+    /// Send every ship of a fleet to a planet, or turn it back to the planet
+    /// it is coming from
+    ///
+    /// The fleet stays together on the way, moving at the speed of its
+    /// slowest ship.
+    pub fn send_fleet(
+        &mut self,
+        race_id: RaceId,
+        name: &str,
+        destination: PlanetId,
+    ) -> Result<(), GameError> {
+        let fleet = self
+            .fleets()
+            .fleet_by_name(race_id, name)
+            .ok_or_else(|| GameError::UnknownFleet(name.to_string()))?
+            .id();
+        let ships = self.fleets().fleet_ships(fleet);
+        self.send_ships(race_id, &ships, destination)
+    }
+
+    /// Order a group to go after enemy ships leaving a planet
+    ///
+    /// As GalaxyNG intercept orders, the group heads for the destination of
+    /// the heaviest group of a race it is at war with on its way from that
+    /// planet; ships outside any group count on their own. The target is
+    /// picked when the turn runs, once every race has given its orders, and
+    /// a later intercept order for the same group replaces this one.
+    pub fn intercept(
+        &mut self,
+        race_id: RaceId,
+        group_id: GroupId,
+        planet: PlanetId,
+    ) -> Result<(), GameError> {
+        self.own_group(race_id, group_id)?;
+        if self.galaxy().get_planet(planet).is_none() {
+            return Err(GameError::UnknownPlanet(planet));
+        }
+
+        let intercepts = &mut self.fleets_mut().intercepts;
+        intercepts.retain(|intercept| intercept.group != group_id);
+        intercepts.push(Intercept {
+            race: race_id,
+            group: group_id,
+            planet,
+        });
+        Ok(())
+    }
+
+    /// Send every group with an intercept order after its target
+    ///
+    /// All targets are picked before any group sets off, so the outcome
+    /// does not depend on the order the races are handled in. Orders that
+    /// cannot be carried out are reported as rejected.
+    pub(crate) fn resolve_intercepts(&mut self) {
+        let intercepts = std::mem::take(&mut self.fleets_mut().intercepts);
+        let targets: Vec<(Intercept, Result<PlanetId, GameError>)> = intercepts
+            .into_iter()
+            .map(|intercept| {
+                let target = self
+                    .own_group(intercept.race, intercept.group)
+                    .and_then(|_| self.intercept_target(intercept.race, intercept.planet));
+                (intercept, target)
+            })
+            .collect();
+
+        for (intercept, target) in targets {
+            let result = target.and_then(|target| {
+                let ships = self
+                    .own_group(intercept.race, intercept.group)?
+                    .ships()
+                    .to_vec();
+                self.send_ships(intercept.race, &ships, target)
+            });
+            if let Err(error) = result {
+                self.record(TurnEvent::OrderRejected {
+                    race: intercept.race,
+                    error,
+                });
+            }
+        }
+    }
+
+    /// Destination of the heaviest group of a race at war with `race_id` on
+    /// its way from a planet
+    fn intercept_target(&self, race_id: RaceId, planet: PlanetId) -> Result<PlanetId, GameError> {
+        let outbound: BTreeMap<ShipId, (PlanetId, f64)> = self
+            .ships()
+            .filter(|ship| self.diplomacy().should_attack(race_id, ship.owner()))
+            .filter_map(|ship| match *ship.location() {
+                ShipLocation::Traveling { from, to, .. } if from == planet => {
+                    Some((ship.id(), (to, ship.design().ship_mass())))
+                }
+                _ => None,
+            })
+            .collect();
+        let group_mass = |ship: ShipId, mass: f64| match self.fleets().group_of(ship) {
+            Some(group) => group
+                .ships()
+                .iter()
+                .filter_map(|id| outbound.get(id))
+                .map(|(_, mass)| mass)
+                .sum(),
+            None => mass,
+        };
+        outbound
+            .iter()
+            .map(|(ship, (to, mass))| (*to, group_mass(*ship, *mass)))
+            .fold(
+                None,
+                |best: Option<(PlanetId, f64)>, (to, mass)| match best {
+                    Some((_, best_mass)) if best_mass >= mass => best,
+                    _ => Some((to, mass)),
+                },
+            )
+            .map(|(to, _)| to)
+            .ok_or(GameError::NothingToIntercept(planet))
+    }
+
+    /// Form a new fleet from ships waiting at one planet and send it off
+    ///
+    /// The ships leave their old groups and are sorted into new groups of
+    /// identical ships; nothing changes if the ships cannot be sent. The fleet
+    /// gets the first `Fleet{n}` name the race does not use yet.
+    pub(crate) fn send_as_fleet(
+        &mut self,
+        race_id: RaceId,
+        ships: &[ShipId],
+        destination: PlanetId,
+    ) -> Result<FleetId, GameError> {
+        self.docked_together(race_id, ships)?;
+        self.check_send(race_id, ships, destination)?;
+        let mut groups: Vec<Vec<ShipId>> = Vec::new();
+        for id in ships {
+            let ship = self.get_ship(*id).expect("ship checked");
+            let group = groups
+                .iter_mut()
+                .find(|group| identical(self.get_ship(group[0]).expect("ship checked"), ship));
+            match group {
+                Some(group) => group.push(*id),
+                None => groups.push(vec![*id]),
+            }
+        }
+
+        let fleets = self.fleets_mut();
+        for group in fleets.groups.values_mut() {
+            group.ships.retain(|ship| !ships.contains(ship));
+        }
+        let name = (fleets.next_fleet_id..)
+            .map(|n| format!("Fleet{n}"))
+            .find(|name| fleets.fleet_by_name(race_id, name).is_none())
+            .expect("unused fleet name");
+        let fleet = fleets.add_fleet(race_id, name);
+        for ships in groups {
+            let group = fleets.add_group(race_id, ships);
+            fleets.groups.get_mut(&group).expect("group added").fleet = Some(fleet);
+        }
+        self.prune_fleets();
+        let ships = self.fleets().fleet_ships(fleet);
+        self.send_ships(race_id, &ships, destination)?;
+        Ok(fleet)
+    }
+
+    /// Move a ship that no longer matches the rest of its group, after
+    /// loading, unloading or an upgrade, into a group of its own in the same
+    /// fleet
+    pub(crate) fn split_off_changed(&mut self, ship_id: ShipId) {
+        let Some(group) = self.fleets().group_of(ship_id) else {
+            return;
+        };
+        let ship = self.get_ship(ship_id).expect("grouped ship exists");
+        if group
+            .ships()
+            .iter()
+            .filter_map(|id| self.get_ship(*id))
+            .all(|other| identical(ship, other))
+        {
+            return;
+        }
+
+        let (group_id, owner, fleet) = (group.id(), group.owner(), group.fleet());
+        let fleets = self.fleets_mut();
+        fleets
+            .groups
+            .get_mut(&group_id)
+            .expect("group exists")
+            .ships
+            .retain(|ship| *ship != ship_id);
+        let split = fleets.add_group(owner, vec![ship_id]);
+        fleets.groups.get_mut(&split).expect("group added").fleet = fleet;
+    }
+
+    /// Speed of every ship travelling with a fleet: that of the slowest ship
+    /// of the fleet on the same leg
+    pub(crate) fn fleet_speeds(&self) -> BTreeMap<ShipId, f64> {
+        let mut legs: BTreeMap<(FleetId, PlanetId, PlanetId), (f64, Vec<ShipId>)> = BTreeMap::new();
+        for group in self.fleets().groups() {
+            let Some(fleet) = group.fleet() else {
+                continue;
+            };
+            for ship in group.ships().iter().filter_map(|id| self.get_ship(*id)) {
+                if let ShipLocation::Traveling { from, to, .. } = *ship.location() {
+                    let (speed, ships) = legs
+                        .entry((fleet, from, to))
+                        .or_insert((f64::INFINITY, Vec::new()));
+                    *speed = speed.min(ship.travel_speed());
+                    ships.push(ship.id());
+                }
+            }
+        }
+
+        legs.into_values()
+            .flat_map(|(speed, ships)| ships.into_iter().map(move |ship| (ship, speed)))
+            .collect()
+    }
+
+    /// Forget destroyed ships, and the groups and fleets left empty
+    pub(crate) fn prune_fleets(&mut self) {
+        let ships: Vec<ShipId> = self.ships().map(|ship| ship.id()).collect();
+        self.fleets_mut()
+            .retain_ships(|ship| ships.binary_search(&ship).is_ok());
+    }
+
+    /// Send ships waiting together at a planet, or turn them back when they
+    /// are on their way from the destination
+    fn send_ships(
+        &mut self,
+        race_id: RaceId,
+        ships: &[ShipId],
+        destination: PlanetId,
+    ) -> Result<(), GameError> {
+        self.check_send(race_id, ships, destination)?;
+        for ship in ships {
+            self.order_ship_travel(*ship, destination)?;
+        }
+        Ok(())
+    }
+
+    /// Check that ships can set off (or turn back) to a destination together
+    ///
+    /// Every ship must either be on its way from the destination or wait at
+    /// the one planet the others set off from.
+    fn check_send(
+        &self,
+        race_id: RaceId,
+        ships: &[ShipId],
+        destination: PlanetId,
+    ) -> Result<(), GameError> {
+        let mut docked = Vec::new();
+        for id in ships {
+            let ship = self.get_ship(*id).ok_or(GameError::UnknownShip(*id))?;
+            match *ship.location() {
+                ShipLocation::Traveling { from, .. } if from == destination => {
+                    if ship.owner() != race_id {
+                        return Err(GameError::NotShipOwner {
+                            ship: *id,
+                            race: race_id,
+                        });
+                    }
+                }
+                _ => docked.push(*id),
+            }
+        }
+        let Some(origin) = self.docked_together(race_id, &docked)? else {
+            return Ok(());
+        };
+        if origin == destination {
+            return Err(GameError::AlreadyAtDestination {
+                ship: docked[0],
+                planet: destination,
+            });
+        }
+        if self.galaxy().get_planet(destination).is_none() {
+            return Err(GameError::UnknownPlanet(destination));
+        }
+        Ok(())
+    }
+
+    fn own_group(&self, race_id: RaceId, group_id: GroupId) -> Result<&Group, GameError> {
+        let group = self
+            .fleets()
+            .get_group(group_id)
+            .ok_or(GameError::UnknownGroup(group_id))?;
+        if group.owner() != race_id {
+            return Err(GameError::NotGroupOwner {
+                group: group_id,
+                race: race_id,
+            });
+        }
+        Ok(group)
+    }
+
+    /// Check that the race's ships all wait at one planet and return it
+    fn docked_together(
+        &self,
+        race_id: RaceId,
+        ships: &[ShipId],
+    ) -> Result<Option<PlanetId>, GameError> {
+        let mut planet = None;
+        for id in ships {
+            let ship = self.get_ship(*id).ok_or(GameError::UnknownShip(*id))?;
+            if ship.owner() != race_id {
+                return Err(GameError::NotShipOwner {
+                    ship: *id,
+                    race: race_id,
+                });
+            }
+            let here = ship
+                .location()
+                .planet_id()
+                .ok_or(GameError::ShipInFlight(*id))?;
+            match planet {
+                Some(planet) if planet != here => {
+                    return Err(GameError::NotTogether { ship: *id, planet });
+                }
+                _ => planet = Some(here),
+            }
+        }
+        Ok(planet)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diplomacy::Relationship;
+    use crate::planet::Position;
+    use crate::race::RESEARCH_PER_LEVEL;
+    use crate::race::TechnologyType;
+    use crate::ship::ShipDesign;
+
+    fn game_with_ships() -> (GameState, RaceId, PlanetId, PlanetId) {
+        let mut game = GameState::new(1000.0, 1000.0);
+        let home = game
+            .galaxy_mut()
+            .add_planet(Position::new(100.0, 100.0), 100, Some(0));
+        let target = game
+            .galaxy_mut()
+            .add_planet(Position::new(200.0, 100.0), 50, None);
+        let race = game.add_race("Humans".to_string(), home.0);
+        game.galaxy_mut()
+            .get_planet_mut(home)
+            .unwrap()
+            .add_materials(500.0);
+        (game, race, home, target)
+    }
+
+    #[test]
+    fn test_merge_and_split_groups() {
+        let (mut game, race, home, target) = game_with_ships();
+        let scout = ShipDesign::new(1.0, 0, 0.0, 0.0, 0.0);
+        let ships: Vec<ShipId> = (0..4)
+            .map(|_| game.build_ship(home, scout).unwrap())
+            .collect();
+        let freighter = game
+            .build_ship(home, ShipDesign::new(1.0, 0, 0.0, 0.0, 1.0))
+            .unwrap();
+
+        let group = game.merge_ships(race, &ships[..2]).unwrap();
+        assert_eq!(
+            game.merge_ships(race, &[ships[0], freighter]),
+            Err(GameError::ShipsDiffer {
+                ship: freighter,
+                other: ships[0],
+            })
+        );
+        // Merging into an existing group keeps its number
+        assert_eq!(game.merge_ships(race, &[ships[0], ships[2]]), Ok(group));
+        assert_eq!(game.fleets().get_group(group).unwrap().ships(), &ships[..3]);
+
+        game.order_ship_travel(ships[3], target).unwrap();
+        assert_eq!(
+            game.merge_ships(race, &[ships[0], ships[3]]),
+            Err(GameError::ShipInFlight(ships[3]))
+        );
+
+        assert_eq!(
+            game.split_group(race, group, 3),
+            Err(GameError::InvalidSplit { group, count: 3 })
+        );
+        let split = game.split_group(race, group, 2).unwrap();
+        assert_eq!(
+            game.fleets().get_group(split).unwrap().ships(),
+            &ships[1..3]
+        );
+        assert_eq!(game.fleets().group_of(ships[0]).unwrap().id(), group);
+        assert_eq!(
+            game.split_group(RaceId(7), split, 1),
+            Err(GameError::NotGroupOwner {
+                group: split,
+                race: RaceId(7),
+            })
+        );
+
+        // Merging every ship of a group away dissolves it, and its fleet
+        let fleet = game.join_fleet(race, split, "Escort").unwrap();
+        game.merge_ships(race, &[ships[0], ships[1], ships[2]])
+            .unwrap();
+        assert!(game.fleets().get_group(split).is_none());
+        assert!(game.fleets().get_fleet(fleet).is_none());
+        assert_eq!(game.merge_ships(race, &[]), Err(GameError::EmptyGroup));
+    }
+
+    #[test]
+    fn test_changed_ships_leave_their_group() {
+        let (mut game, race, home, _) = game_with_ships();
+        let freighter = ShipDesign::new(1.0, 0, 0.0, 0.0, 1.0);
+        let ships: Vec<ShipId> = (0..3)
+            .map(|_| game.build_ship(home, freighter).unwrap())
+            .collect();
+        let group = game.merge_ships(race, &ships).unwrap();
+        let fleet = game.join_fleet(race, group, "Convoy").unwrap();
+
+        // Loading one ship takes it out of the group, but not the fleet
+        game.load_ship(ships[0], CargoType::Materials, None)
+            .unwrap();
+        let loaded = game.fleets().group_of(ships[0]).unwrap();
+        assert_ne!(loaded.id(), group);
+        assert_eq!(loaded.ships(), &ships[..1]);
+        assert_eq!(loaded.fleet(), Some(fleet));
+        assert_eq!(game.fleets().get_group(group).unwrap().ships(), &ships[1..]);
+
+        // So does an upgrade
+        game.get_race_mut(race)
+            .unwrap()
+            .add_research(TechnologyType::Drive, RESEARCH_PER_LEVEL);
+        game.upgrade_ship(ships[1]).unwrap();
+        let upgraded = game.fleets().group_of(ships[1]).unwrap();
+        assert_ne!(upgraded.id(), group);
+        assert_eq!(upgraded.fleet(), Some(fleet));
+        assert_eq!(game.fleets().get_group(group).unwrap().ships(), &ships[2..]);
+
+        // A ship alone in its group keeps it
+        let alone = game.fleets().group_of(ships[0]).unwrap().id();
+        game.unload_ship(ships[0], CargoType::Materials, None)
+            .unwrap();
+        assert_eq!(game.fleets().group_of(ships[0]).unwrap().id(), alone);
+    }
+
+    #[test]
+    fn test_intercept_and_turn_back() {
+        let (mut game, race, home, target) = game_with_ships();
+        let far = game
+            .galaxy_mut()
+            .add_planet(Position::new(100.0, 300.0), 50, None);
+        let enemy_home = game
+            .galaxy_mut()
+            .add_planet(Position::new(300.0, 100.0), 100, Some(1));
+        game.add_race("Zorgs".to_string(), enemy_home.0);
+        game.galaxy_mut()
+            .get_planet_mut(enemy_home)
+            .unwrap()
+            .add_materials(100.0);
+        let raider = game
+            .build_ship(enemy_home, ShipDesign::new(5.0, 1, 5.0, 0.0, 0.0))
+            .unwrap();
+        // Three scouts outweigh the raider together, but not one by one
+        let scouts: Vec<ShipId> = (0..3)
+            .map(|_| {
+                game.build_ship(enemy_home, ShipDesign::new(4.0, 0, 0.0, 0.0, 0.0))
+                    .unwrap()
+            })
+            .collect();
+        // A heavy freighter of a race we are not at war with
+        let neutral_home = game
+            .galaxy_mut()
+            .add_planet(Position::new(300.0, 300.0), 100, Some(2));
+        game.add_race("Traders".to_string(), neutral_home.0);
+        game.galaxy_mut()
+            .get_planet_mut(neutral_home)
+            .unwrap()
+            .add_materials(100.0);
+        let freighter = game
+            .build_ship(neutral_home, ShipDesign::new(10.0, 0, 0.0, 0.0, 40.0))
+            .unwrap();
+        let guard = game
+            .build_ship(home, ShipDesign::new(5.0, 1, 5.0, 0.0, 0.0))
+            .unwrap();
+        let group = game.merge_ships(race, &[guard]).unwrap();
+
+        game.intercept(race, group, enemy_home).unwrap();
+        assert!(game.advance_turn().contains(&TurnEvent::OrderRejected {
+            race,
+            error: GameError::NothingToIntercept(enemy_home),
+        }));
+        game.order_ship_travel(raider, target).unwrap();
+        for scout in &scouts {
+            game.order_ship_travel(*scout, far).unwrap();
+        }
+        game.get_ship_mut(freighter)
+            .unwrap()
+            .set_location(ShipLocation::Traveling {
+                from: enemy_home,
+                to: far,
+                progress: 0.0,
+            });
+        game.advance_turn();
+        // Only races at war are intercepted
+        game.intercept(race, group, enemy_home).unwrap();
+        game.resolve_intercepts();
+        assert_eq!(
+            game.get_ship(guard).unwrap().location(),
+            &ShipLocation::AtPlanet(home)
+        );
+        game.set_relationship(race, RaceId(1), Relationship::Hostile);
+
+        // The raider is the heaviest group, not the scouts
+        game.intercept(race, group, enemy_home).unwrap();
+        game.resolve_intercepts();
+        assert!(matches!(
+            game.get_ship(guard).unwrap().location(),
+            ShipLocation::Traveling { to, .. } if *to == target
+        ));
+        game.advance_turn();
+        let ShipLocation::Traveling { progress, .. } = *game.get_ship(guard).unwrap().location()
+        else {
+            panic!("guard should be on its way");
+        };
+
+        // A fleet on its way can turn back, keeping the distance covered
+        let fleet = game.join_fleet(race, group, "Home Guard");
+        assert_eq!(fleet, Err(GameError::ShipInFlight(guard)));
+        game.order_ship_travel(guard, home).unwrap();
+        assert_eq!(
+            game.get_ship(guard).unwrap().location(),
+            &ShipLocation::Traveling {
+                from: target,
+                to: home,
+                progress: 1.0 - progress,
+            }
+        );
+    }
+
+    #[test]
+    fn test_fleet_is_sent_whole_or_not_at_all() {
+        let (mut game, race, home, target) = game_with_ships();
+        let far = game
+            .galaxy_mut()
+            .add_planet(Position::new(100.0, 300.0), 50, None);
+        let scout = ShipDesign::new(1.0, 0, 0.0, 0.0, 0.0);
+        let ships: Vec<ShipId> = (0..2)
+            .map(|_| game.build_ship(home, scout).unwrap())
+            .collect();
+        let group = game.merge_ships(race, &ships).unwrap();
+        game.join_fleet(race, group, "Pickets").unwrap();
+        let turning_back = ShipLocation::Traveling {
+            from: target,
+            to: home,
+            progress: 0.5,
+        };
+        game.get_ship_mut(ships[0])
+            .unwrap()
+            .set_location(turning_back);
+        game.get_ship_mut(ships[1])
+            .unwrap()
+            .set_location(ShipLocation::Traveling {
+                from: far,
+                to: home,
+                progress: 0.5,
+            });
+
+        // One ship could turn back, the other is bound elsewhere
+        assert_eq!(
+            game.send_fleet(race, "Pickets", target),
+            Err(GameError::ShipInFlight(ships[1]))
+        );
+        assert_eq!(game.get_ship(ships[0]).unwrap().location(), &turning_back);
+    }
+
+    #[test]
+    fn test_intercept_sees_ships_ordered_off_later() {
+        let (mut game, race, home, target) = game_with_ships();
+        let enemy_home = game
+            .galaxy_mut()
+            .add_planet(Position::new(300.0, 100.0), 100, Some(1));
+        let enemy = game.add_race("Zorgs".to_string(), enemy_home.0);
+        game.galaxy_mut()
+            .get_planet_mut(enemy_home)
+            .unwrap()
+            .add_materials(100.0);
+        let raider = game
+            .build_ship(enemy_home, ShipDesign::new(5.0, 1, 5.0, 0.0, 0.0))
+            .unwrap();
+        let guard = game
+            .build_ship(home, ShipDesign::new(5.0, 1, 5.0, 0.0, 0.0))
+            .unwrap();
+        let group = game.merge_ships(race, &[guard]).unwrap();
+        game.set_relationship(race, enemy, Relationship::Hostile);
+
+        // Our orders are handled before the raider is sent off
+        game.apply_orders(race, &format!("i {} {}", group.0, enemy_home.0))
+            .unwrap();
+        game.apply_orders(enemy, &format!("s {} {}", raider.0, target.0))
+            .unwrap();
+        game.advance_turn();
+
+        assert!(matches!(
+            game.get_ship(guard).unwrap().location(),
+            ShipLocation::Traveling { to, .. } if *to == target
+        ));
+        assert_eq!(
+            game.apply_orders(race, &format!("i {} 99", group.0)),
+            Err(vec![crate::orders::OrderLineError {
+                line: 1,
+                error: GameError::UnknownPlanet(PlanetId(99)).into(),
+            }])
+        );
+    }
+
+    #[test]
+    fn test_racebot_sends_ships_as_fleet() {
+        let (mut game, race, home, _) = game_with_ships();
+        let fast = game
+            .build_ship(home, ShipDesign::new(10.0, 0, 0.0, 0.0, 0.0))
+            .unwrap();
+        let slow = game
+            .build_ship(home, ShipDesign::new(2.0, 0, 0.0, 0.0, 8.0))
+            .unwrap();
+
+        game.run_racebot(race);
+
+        let group = game.fleets().group_of(fast).unwrap();
+        let fleet = group.fleet().unwrap();
+        assert_eq!(game.fleets().fleet_ships(fleet), vec![fast, slow]);
+        let speeds = game.fleet_speeds();
+        assert!(speeds.contains_key(&fast));
+        assert_eq!(speeds.get(&fast), speeds.get(&slow));
+    }
+
+    #[test]
+    fn test_racebot_fleet_names_do_not_clash() {
+        let (mut game, race, home, target) = game_with_ships();
+        let scout = ShipDesign::new(1.0, 0, 0.0, 0.0, 0.0);
+        let ships: Vec<ShipId> = (0..4)
+            .map(|_| game.build_ship(home, scout).unwrap())
+            .collect();
+        let group = game.merge_ships(race, &ships[..2]).unwrap();
+        let own = game.join_fleet(race, group, "Fleet1").unwrap();
+
+        // A fleet that cannot be sent leaves the groups alone
+        assert_eq!(
+            game.send_as_fleet(race, &ships[1..], home),
+            Err(GameError::AlreadyAtDestination {
+                ship: ships[1],
+                planet: home,
+            })
+        );
+        assert_eq!(game.fleets().fleet_ships(own), &ships[..2]);
+
+        let fleet = game.send_as_fleet(race, &ships[2..], target).unwrap();
+        assert_ne!(fleet, own);
+        assert_eq!(game.fleets().get_fleet(fleet).unwrap().name(), "Fleet2");
+        assert_eq!(game.fleets().fleet_ships(own), &ships[..2]);
+        assert_eq!(game.fleets().fleet_ships(fleet), &ships[2..]);
+        assert_eq!(
+            game.get_ship(ships[0]).unwrap().location(),
+            &ShipLocation::AtPlanet(home)
+        );
+    }
+
+    #[test]
+    fn test_fleet_moves_at_slowest_speed() {
+        let (mut game, race, home, target) = game_with_ships();
+        let fast = game
+            .build_ship(home, ShipDesign::new(10.0, 0, 0.0, 0.0, 0.0))
+            .unwrap();
+        let slow = game
+            .build_ship(home, ShipDesign::new(2.0, 0, 0.0, 0.0, 8.0))
+            .unwrap();
+        let fast_group = game.merge_ships(race, &[fast]).unwrap();
+        let slow_group = game.merge_ships(race, &[slow]).unwrap();
+        let fleet = game.join_fleet(race, fast_group, "Armada").unwrap();
+        assert_eq!(game.join_fleet(race, slow_group, "Armada"), Ok(fleet));
+        assert_eq!(
+            game.send_fleet(race, "Navy", target),
+            Err(GameError::UnknownFleet("Navy".to_string()))
+        );
+
+        game.send_fleet(race, "Armada", target).unwrap();
+        let slow_speed = game.get_ship(slow).unwrap().travel_speed();
+        assert!(slow_speed < game.get_ship(fast).unwrap().travel_speed());
+        assert_eq!(game.fleet_speeds().get(&fast), Some(&slow_speed));
+        game.advance_turn();
+        assert_eq!(
+            game.get_ship(fast).unwrap().location(),
+            game.get_ship(slow).unwrap().location()
+        );
+        assert_eq!(
+            game.join_fleet(race, fast_group, "Escort"),
+            Err(GameError::ShipInFlight(fast))
+        );
+    }
+}
@@ -1,15 +1,20 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
-use bevy::prelude::*;
+#[cfg(feature = "bevy")]
+use bevy::prelude::Resource;
+use serde::Deserialize;
+use serde::Serialize;
 
 use crate::planet::Planet;
 use crate::planet::PlanetId;
 use crate::planet::Position;
+use crate::race::TechnologyType;
 
 /// The galaxy containing all planets
-#[derive(Debug, Resource)]
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(Resource))]
 pub struct Galaxy {
-    planets: HashMap<PlanetId, Planet>,
+    planets: BTreeMap<PlanetId, Planet>,
     next_planet_id: u32,
     width: f64,
     height: f64,
@@ -18,7 +23,7 @@ pub struct Galaxy {
 impl Galaxy {
     pub fn new(width: f64, height: f64) -> Self {
         Self {
-            planets: HashMap::new(),
+            planets: BTreeMap::new(),
             next_planet_id: 0,
             width,
             height,
@@ -85,9 +90,16 @@ impl Galaxy {
     }
 
     /// Process production for all planets
-    pub fn execute_production(&mut self) {
-        for planet in self.planets.values_mut() {
-            planet.execute_production();
-        }
+    ///
+    /// Returns the research effort produced as (owner, technology, effort).
+    pub fn execute_production(&mut self) -> Vec<(u32, TechnologyType, f64)> {
+        self.planets
+            .values_mut()
+            .filter_map(|planet| {
+                let owner = planet.owner()?;
+                let (tech_type, effort) = planet.execute_production()?;
+                Some((owner, tech_type, effort))
+            })
+            .collect()
     }
 }
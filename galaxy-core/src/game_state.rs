@@ -1,46 +1,88 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 
-use bevy::prelude::*;
+#[cfg(feature = "bevy")]
+use bevy::prelude::Resource;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::Deserialize;
+use serde::Serialize;
 
+use crate::combat::BattleReport;
 use crate::combat::CombatSystem;
+use crate::combat::PlanetDefences;
 use crate::diplomacy::Diplomacy;
+use crate::diplomacy::Relationship;
+use crate::error::GameError;
+use crate::event::TurnEvent;
+use crate::fleet::Fleets;
 use crate::galaxy::Galaxy;
+use crate::planet::Planet;
 use crate::planet::PlanetId;
-use crate::planet::TechFocus;
+use crate::planet::ProductionType;
+use crate::planet::SCRAP_RECOVERY;
+use crate::planet::ShipTypeId;
 use crate::race::Race;
 use crate::race::RaceId;
 use crate::race::TechnologyType;
+use crate::racebot::CargoOrder;
 use crate::racebot::Personality;
 use crate::racebot::Racebot;
+use crate::ship::CargoType;
 use crate::ship::Ship;
 use crate::ship::ShipDesign;
 use crate::ship::ShipId;
 use crate::ship::ShipLocation;
 
+/// Random number generator driving every random decision in a game
+pub type GameRng = ChaCha8Rng;
+
 /// The main game state
-#[derive(Debug, Resource)]
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(Resource))]
 pub struct GameState {
     galaxy: Galaxy,
-    races: HashMap<RaceId, Race>,
-    ships: HashMap<ShipId, Ship>,
+    races: BTreeMap<RaceId, Race>,
+    ships: BTreeMap<ShipId, Ship>,
     diplomacy: Diplomacy,
-    ai_personalities: HashMap<RaceId, Personality>,
+    /// Groups and fleets the races have formed from their ships
+    #[serde(default)]
+    fleets: Fleets,
+    ai_personalities: BTreeMap<RaceId, Personality>,
     next_race_id: u32,
     next_ship_id: u32,
     turn: u32,
+    rng: GameRng,
+    /// Events of the most recently completed turn
+    events: Vec<TurnEvent>,
+    /// Events recorded since that turn, reported with the next one
+    pending_events: Vec<TurnEvent>,
+    /// Battles fought in the most recent turn
+    #[serde(default)]
+    battles: Vec<BattleReport>,
 }
 
 impl GameState {
     pub fn new(galaxy_width: f64, galaxy_height: f64) -> Self {
+        Self::with_seed(galaxy_width, galaxy_height, 0)
+    }
+
+    /// Create a game whose random stream is derived from `seed`
+    pub fn with_seed(galaxy_width: f64, galaxy_height: f64, seed: u64) -> Self {
         Self {
             galaxy: Galaxy::new(galaxy_width, galaxy_height),
-            races: HashMap::new(),
-            ships: HashMap::new(),
+            races: BTreeMap::new(),
+            ships: BTreeMap::new(),
             diplomacy: Diplomacy::new(),
-            ai_personalities: HashMap::new(),
+            fleets: Fleets::new(),
+            ai_personalities: BTreeMap::new(),
             next_race_id: 0,
             next_ship_id: 0,
             turn: 0,
+            rng: GameRng::seed_from_u64(seed),
+            events: Vec::new(),
+            pending_events: Vec::new(),
+            battles: Vec::new(),
         }
     }
 
@@ -56,6 +98,11 @@ impl GameState {
         self.turn
     }
 
+    /// Game-owned random stream; every random decision must draw from it
+    pub fn rng_mut(&mut self) -> &mut GameRng {
+        &mut self.rng
+    }
+
     pub fn diplomacy(&self) -> &Diplomacy {
         &self.diplomacy
     }
@@ -64,6 +111,42 @@ impl GameState {
         &mut self.diplomacy
     }
 
+    pub fn fleets(&self) -> &Fleets {
+        &self.fleets
+    }
+
+    pub(crate) fn fleets_mut(&mut self) -> &mut Fleets {
+        &mut self.fleets
+    }
+
+    /// Change the relationship between two races, recording the change
+    pub fn set_relationship(&mut self, race1: RaceId, race2: RaceId, relationship: Relationship) {
+        if race1 == race2 || self.diplomacy.get_relationship(race1, race2) == relationship {
+            return;
+        }
+
+        self.diplomacy.set_relationship(race1, race2, relationship);
+        self.record(TurnEvent::RelationshipChanged {
+            race1,
+            race2,
+            relationship,
+        });
+    }
+
+    /// Shot-by-shot reports of the battles in the most recent turn
+    pub fn battles(&self) -> &[BattleReport] {
+        &self.battles
+    }
+
+    /// Events of the most recently completed turn
+    pub fn events(&self) -> &[TurnEvent] {
+        &self.events
+    }
+
+    pub(crate) fn record(&mut self, event: TurnEvent) {
+        self.pending_events.push(event);
+    }
+
     /// Add a new race to the game
     pub fn add_race(&mut self, name: String, home_planet_id: u32) -> RaceId {
         let id = RaceId(self.next_race_id);
@@ -105,76 +188,90 @@ impl GameState {
         self.races.values()
     }
 
-    /// Process one turn of the game
-    pub fn advance_turn(&mut self) {
+    /// Process one turn of the game and return what happened in it
+    ///
+    /// Events caused by orders given since the previous turn are included.
+    pub fn advance_turn(&mut self) -> &[TurnEvent] {
         self.turn += 1;
-        println!("\n=== Turn {} ===", self.turn);
 
         // 0. Process AI decisions for all AI-controlled races
         self.process_ai_turns();
 
         // 1. Execute production on all planets
-        self.galaxy.execute_production();
+        let research = self.galaxy.execute_production();
+        self.process_ship_production();
 
-        // 2. Process technology advancement per planet
-        self.process_technology_advancement();
+        // 2. Credit research production to races
+        self.process_research(research);
 
-        // 3. Process ship movement
+        // 3. Send ships along cargo routes and after the ships they intercept, move
+        //    them and deliver the cargo
+        self.dispatch_routes();
+        self.resolve_intercepts();
         self.process_ship_movement();
+        self.deliver_routed_cargo();
 
         // 4. Process combat encounters
         self.process_combat();
 
         // 5. Process planet bombing and capture
         self.process_planet_bombing();
+        self.prune_fleets();
 
-        // 6. Grow population on all planets
+        // 6. Grow population and rebuild defences on all planets
         self.process_population_growth();
 
-        // Print summary
-        let total_ships = self.ships.len();
-        let owned_planets: usize = self
-            .galaxy
-            .planets()
-            .filter(|p| p.owner().is_some())
-            .count();
-        println!(
-            "Turn {} complete: {} planets owned, {} ships in galaxy",
-            self.turn, owned_planets, total_ships
-        );
+        self.events = std::mem::take(&mut self.pending_events);
+        &self.events
     }
 
     fn process_population_growth(&mut self) {
         for planet in self.galaxy.planets_mut() {
             planet.grow_population();
+            planet.rebuild_defences();
         }
     }
 
-    fn process_technology_advancement(&mut self) {
-        // Collect planet data first to avoid borrow checker issues
-        let planet_research: Vec<(u32, TechnologyType, u32)> = self
+    /// Let planets producing ships work on their owner's design and launch
+    /// whatever got finished
+    fn process_ship_production(&mut self) {
+        let shipyards: Vec<(PlanetId, RaceId, ShipTypeId, ShipDesign)> = self
             .galaxy
             .planets()
-            .filter(|p| p.owner().is_some())
-            .filter_map(|p| match p.tech_focus() {
-                TechFocus::Research(tech_type) => Some((p.owner().unwrap(), tech_type, p.size())),
-                TechFocus::None => None,
+            .filter_map(|p| {
+                let ProductionType::Ships(ship_type) = p.production_type() else {
+                    return None;
+                };
+                let owner = RaceId(p.owner()?);
+                let design = *self.races.get(&owner)?.ship_type(ship_type)?.design();
+                Some((p.id(), owner, ship_type, design))
             })
             .collect();
 
-        // Apply research to races
-        for (race_id, tech_type, planet_size) in planet_research {
-            if let Some(race) = self.get_race_mut(RaceId(race_id)) {
-                let effort = planet_size as f64;
-                race.add_research(tech_type, effort);
+        for (planet_id, owner, ship_type, design) in shipyards {
+            let Some(planet) = self.galaxy.get_planet_mut(planet_id) else {
+                continue;
+            };
+            for _ in 0..planet.produce_ships(&design) {
+                self.spawn_ship(owner, design, Some(ship_type), planet_id);
             }
         }
     }
 
-    /// Set technology focus for a planet
-    pub fn set_planet_tech_focus(&mut self, planet_id: PlanetId, focus: TechFocus) {
-        if let Some(planet) = self.galaxy.get_planet_mut(planet_id) {
-            planet.set_tech_focus(focus);
+    /// Credit research produced by planets to their owners
+    fn process_research(&mut self, research: Vec<(u32, TechnologyType, f64)>) {
+        for (race_id, tech_type, effort) in research {
+            let race_id = RaceId(race_id);
+            if let Some(race) = self.races.get_mut(&race_id)
+                && race.add_research(tech_type, effort)
+            {
+                let level = race.technology().get_level(tech_type);
+                self.record(TurnEvent::TechAdvanced {
+                    race: race_id,
+                    tech: tech_type,
+                    level,
+                });
+            }
         }
     }
 
@@ -217,26 +314,116 @@ impl GameState {
         None // No winner within max_turns
     }
 
-    /// Build a ship at a planet
-    pub fn build_ship(&mut self, planet_id: PlanetId, design: ShipDesign) -> Option<ShipId> {
-        let planet = self.galaxy.get_planet(planet_id)?;
-        let owner_id = planet.owner()?;
+    /// Build a ship at a planet right away, paying its material cost from the
+    /// stockpile
+    ///
+    /// Planets set to [`ProductionType::Ships`] build over several turns
+    /// instead.
+    pub fn build_ship(
+        &mut self,
+        planet_id: PlanetId,
+        design: ShipDesign,
+    ) -> Result<ShipId, GameError> {
+        let planet = self
+            .galaxy
+            .get_planet_mut(planet_id)
+            .ok_or(GameError::UnknownPlanet(planet_id))?;
+        let owner = planet
+            .owner()
+            .map(RaceId)
+            .ok_or(GameError::PlanetUnowned(planet_id))?;
+        design.validate()?;
+
+        planet.consume_materials(design.material_cost())?;
+
+        Ok(self.spawn_ship(owner, design, None, planet_id))
+    }
+
+    /// Build a ship of one of the planet owner's ship types right away
+    pub fn build_ship_of_type(
+        &mut self,
+        planet_id: PlanetId,
+        ship_type: ShipTypeId,
+    ) -> Result<ShipId, GameError> {
+        let planet = self
+            .galaxy
+            .get_planet_mut(planet_id)
+            .ok_or(GameError::UnknownPlanet(planet_id))?;
+        let owner = planet
+            .owner()
+            .map(RaceId)
+            .ok_or(GameError::PlanetUnowned(planet_id))?;
+        let design = *self
+            .races
+            .get(&owner)
+            .and_then(|race| race.ship_type(ship_type))
+            .ok_or_else(|| GameError::UnknownShipType(format!("#{}", ship_type.0)))?
+            .design();
 
-        let cost = design.material_cost();
+        planet.consume_materials(design.material_cost())?;
 
-        // Check if planet has enough materials
-        let planet = self.galaxy.get_planet_mut(planet_id)?;
-        if planet.consume_materials(cost) {
-            let ship_id = ShipId(self.next_ship_id);
-            self.next_ship_id += 1;
+        Ok(self.spawn_ship(owner, design, Some(ship_type), planet_id))
+    }
 
-            let ship = Ship::new(ship_id, RaceId(owner_id), design, planet_id);
-            self.ships.insert(ship_id, ship);
+    /// Delete one of a race's ship types
+    ///
+    /// Types that existing ships were built from, or that a planet is
+    /// producing, cannot be deleted.
+    pub fn delete_ship_type(&mut self, race_id: RaceId, name: &str) -> Result<(), GameError> {
+        let race = self
+            .races
+            .get(&race_id)
+            .ok_or(GameError::UnknownRace(race_id))?;
+        let ship_type = race
+            .ship_type_by_name(name)
+            .ok_or_else(|| GameError::UnknownShipType(name.to_string()))?;
 
-            return Some(ship_id);
+        let has_ships = self
+            .ships
+            .values()
+            .any(|ship| ship.owner() == race_id && ship.ship_type() == Some(ship_type));
+        let in_production = self
+            .galaxy
+            .planets_owned_by(race_id.0)
+            .any(|planet| planet.production_type() == ProductionType::Ships(ship_type));
+        if has_ships || in_production {
+            return Err(GameError::ShipTypeInUse(name.to_string()));
         }
 
-        None
+        if let Some(race) = self.races.get_mut(&race_id) {
+            race.remove_ship_type(ship_type);
+        }
+        Ok(())
+    }
+
+    /// Put a newly finished ship into orbit around a planet
+    fn spawn_ship(
+        &mut self,
+        owner: RaceId,
+        design: ShipDesign,
+        ship_type: Option<ShipTypeId>,
+        planet_id: PlanetId,
+    ) -> ShipId {
+        let ship_id = ShipId(self.next_ship_id);
+        self.next_ship_id += 1;
+
+        let tech = self
+            .races
+            .get(&owner)
+            .map(|race| race.technology().clone())
+            .unwrap_or_default();
+        let mut ship = Ship::new(ship_id, owner, design, planet_id).with_tech(tech);
+        if let Some(ship_type) = ship_type {
+            ship = ship.with_ship_type(ship_type);
+        }
+        self.ships.insert(ship_id, ship);
+        self.record(TurnEvent::ShipBuilt {
+            ship: ship_id,
+            race: owner,
+            planet: planet_id,
+        });
+
+        ship_id
     }
 
     /// Get a ship by ID
@@ -244,32 +431,59 @@ impl GameState {
         self.ships.get(&id)
     }
 
+    pub(crate) fn get_ship_mut(&mut self, id: ShipId) -> Option<&mut Ship> {
+        self.ships.get_mut(&id)
+    }
+
     /// Get all ships
     pub fn ships(&self) -> impl Iterator<Item = &Ship> {
         self.ships.values()
     }
 
     /// Order a ship to travel to a destination planet
-    pub fn order_ship_travel(&mut self, ship_id: ShipId, destination: PlanetId) -> bool {
-        let ship = match self.ships.get_mut(&ship_id) {
-            Some(s) => s,
-            None => return false,
-        };
+    ///
+    /// Ships in flight can only turn back to the planet they came from; they
+    /// have as far to go back as they have come.
+    pub fn order_ship_travel(
+        &mut self,
+        ship_id: ShipId,
+        destination: PlanetId,
+    ) -> Result<(), GameError> {
+        let ship = self
+            .ships
+            .get_mut(&ship_id)
+            .ok_or(GameError::UnknownShip(ship_id))?;
 
         // Get current location
-        let origin = match ship.location() {
-            ShipLocation::AtPlanet(planet_id) => *planet_id,
-            ShipLocation::Traveling { .. } => return false, // Already traveling
+        let origin = match *ship.location() {
+            ShipLocation::AtPlanet(planet_id) => planet_id,
+            ShipLocation::Traveling { from, to, progress } if from == destination => {
+                ship.set_location(if progress > 0.0 {
+                    ShipLocation::Traveling {
+                        from: to,
+                        to: from,
+                        progress: 1.0 - progress,
+                    }
+                } else {
+                    // It has not left yet
+                    ShipLocation::AtPlanet(from)
+                });
+                return Ok(());
+            }
+            ShipLocation::Traveling { .. } => return Err(GameError::ShipInFlight(ship_id)),
         };
 
         // Can't travel to same planet
         if origin == destination {
-            return false;
+            return Err(GameError::AlreadyAtDestination {
+                ship: ship_id,
+                planet: destination,
+            });
         }
 
         // Verify destination exists
         if self.galaxy.get_planet(destination).is_none() {
-            return false;
+            return Err(GameError::UnknownPlanet(destination));
         }
 
         // Start travel
@@ -279,7 +493,190 @@ impl GameState {
             progress: 0.0,
         });
 
-        true
+        Ok(())
+    }
+
+    /// Move cargo from the planet a ship is orbiting into its hold
+    ///
+    /// Loads as much as fits in the ship's cargo space (at the cargo tech it
+    /// was built or upgraded with) when no amount is given, and at most what
+    /// the planet has in stock. Returns the amount loaded; the ship must be at
+    /// a planet owned by its race.
+    pub fn load_ship(
+        &mut self,
+        ship_id: ShipId,
+        cargo: CargoType,
+        amount: Option<f64>,
+    ) -> Result<f64, GameError> {
+        let (ship, planet) = self.docked_ship_mut(ship_id)?;
+        let cargo_tech = ship.tech().cargo_level() as f64;
+        if ship.available_cargo(cargo_tech) <= 0.0 {
+            return Err(GameError::CargoHoldFull(ship_id));
+        }
+
+        let stockpile = match cargo {
+            CargoType::Colonists => planet.colonists(),
+            CargoType::Materials => planet.materials(),
+            CargoType::Capital => planet.capital(),
+        };
+        let wanted = amount.unwrap_or_else(|| ship.available_cargo(cargo_tech));
+        if stockpile <= 0.0 {
+            return Err(GameError::InsufficientStockpile {
+                planet: planet.id(),
+                resource: cargo,
+                needed: wanted,
+                available: stockpile,
+            });
+        }
+
+        let loaded = ship.load_cargo(cargo, wanted.min(stockpile), cargo_tech)?;
+        match cargo {
+            CargoType::Colonists => planet.consume_colonists(loaded),
+            CargoType::Materials => planet.consume_materials(loaded),
+            CargoType::Capital => planet.consume_capital(loaded),
+        }?;
+        self.split_off_changed(ship_id);
+        Ok(loaded)
+    }
+
+    /// Move cargo from a ship's hold onto the planet it is orbiting
+    ///
+    /// Unloads everything of that type when no amount is given and returns
+    /// the amount unloaded. Following GalaxyNG:
+    ///
+    /// - materials and capital can be unloaded at any planet; on a foreign
+    ///   planet they go to its owner's stockpile, on an unowned one they stay
+    ///   for whoever settles it
+    /// - colonists can be unloaded at the race's own planets, or landed on an
+    ///   unowned planet to colonize it
+    pub fn unload_ship(
+        &mut self,
+        ship_id: ShipId,
+        cargo: CargoType,
+        amount: Option<f64>,
+    ) -> Result<f64, GameError> {
+        if let Some(amount) = amount
+            && (amount.is_nan() || amount <= 0.0)
+        {
+            return Err(GameError::InvalidAmount(amount));
+        }
+        let ship = self
+            .ships
+            .get_mut(&ship_id)
+            .ok_or(GameError::UnknownShip(ship_id))?;
+        let planet_id = ship
+            .location()
+            .planet_id()
+            .ok_or(GameError::ShipInFlight(ship_id))?;
+        let planet = self
+            .galaxy
+            .get_planet_mut(planet_id)
+            .ok_or(GameError::UnknownPlanet(planet_id))?;
+        let race = ship.owner();
+        let foreign = planet.owner().is_some_and(|owner| owner != race.0);
+        if cargo == CargoType::Colonists && foreign {
+            return Err(GameError::NotPlanetOwner {
+                planet: planet_id,
+                race,
+            });
+        }
+
+        let unloaded = ship.unload_cargo(cargo, amount.unwrap_or(f64::INFINITY));
+        let mut colonized = None;
+        match cargo {
+            CargoType::Colonists if planet.owner().is_none() => {
+                if unloaded > 0.0 {
+                    colonized = Some(planet.previous_owner().map(RaceId));
+                    planet.colonize(race.0, unloaded);
+                }
+            }
+            CargoType::Colonists => planet.add_colonists(unloaded),
+            CargoType::Materials => planet.add_materials(unloaded),
+            CargoType::Capital => planet.add_capital(unloaded),
+        }
+
+        match colonized {
+            Some(Some(previous_owner)) if previous_owner != race => {
+                self.record(TurnEvent::PlanetCaptured {
+                    planet: planet_id,
+                    previous_owner,
+                    new_owner: race,
+                });
+            }
+            Some(_) => self.record(TurnEvent::PlanetColonized {
+                planet: planet_id,
+                race,
+            }),
+            None => {}
+        }
+        self.split_off_changed(ship_id);
+        Ok(unloaded)
+    }
+
+    /// Bring a ship up to its race's current tech levels
+    ///
+    /// The ship must orbit a planet of its own race, which pays the GalaxyNG
+    /// upgrade cost out of this turn's production. Returns the production
+    /// spent.
+    pub fn upgrade_ship(&mut self, ship_id: ShipId) -> Result<f64, GameError> {
+        let owner = self
+            .ships
+            .get(&ship_id)
+            .ok_or(GameError::UnknownShip(ship_id))?
+            .owner();
+        let tech = self
+            .races
+            .get(&owner)
+            .ok_or(GameError::UnknownRace(owner))?
+            .technology()
+            .clone();
+        let (ship, planet) = self.docked_ship_mut(ship_id)?;
+
+        let cost = ship.upgrade_cost(&tech);
+        planet.spend_production(cost)?;
+        ship.upgrade(tech);
+        self.split_off_changed(ship_id);
+        Ok(cost)
+    }
+
+    /// Break up a ship at a planet of its own race
+    ///
+    /// The planet recovers [`SCRAP_RECOVERY`] of the ship's material cost as
+    /// materials and takes whatever was in its hold. Returns the materials
+    /// recovered from the ship itself.
+    pub fn scrap_ship(&mut self, ship_id: ShipId) -> Result<f64, GameError> {
+        let (ship, planet) = self.docked_ship_mut(ship_id)?;
+
+        let recovered = ship.design().material_cost() * SCRAP_RECOVERY;
+        planet.add_materials(recovered + ship.cargo(CargoType::Materials));
+        planet.add_capital(ship.cargo(CargoType::Capital));
+        planet.add_colonists(ship.cargo(CargoType::Colonists));
+        self.ships.remove(&ship_id);
+        self.prune_fleets();
+        Ok(recovered)
+    }
+
+    /// A ship together with the planet of its own race it is orbiting
+    fn docked_ship_mut(&mut self, ship_id: ShipId) -> Result<(&mut Ship, &mut Planet), GameError> {
+        let ship = self
+            .ships
+            .get_mut(&ship_id)
+            .ok_or(GameError::UnknownShip(ship_id))?;
+        let planet_id = ship
+            .location()
+            .planet_id()
+            .ok_or(GameError::ShipInFlight(ship_id))?;
+        let planet = self
+            .galaxy
+            .get_planet_mut(planet_id)
+            .ok_or(GameError::UnknownPlanet(planet_id))?;
+        if planet.owner() != Some(ship.owner().0) {
+            return Err(GameError::NotPlanetOwner {
+                planet: planet_id,
+                race: ship.owner(),
+            });
+        }
+        Ok((ship, planet))
     }
 
     fn process_ship_movement(&mut self) {
@@ -300,26 +697,30 @@ impl GameState {
             })
             .collect();
 
+        // Ships in a fleet keep pace with its slowest ship
+        let fleet_speeds = self.fleet_speeds();
+        let mut events = Vec::new();
+
         for (ship_id, from, to, progress, distance) in movements {
             if let Some(ship) = self.ships.get_mut(&ship_id) {
-                // Get drive technology for this ship's owner
-                let drive_tech = self
-                    .races
-                    .get(&ship.owner())
-                    .map_or(1.0, |r| r.technology().drive_level() as f64);
-
-                let speed = ship.travel_speed(drive_tech);
+                let speed = fleet_speeds
+                    .get(&ship_id)
+                    .copied()
+                    .unwrap_or_else(|| ship.travel_speed());
                 let new_progress = progress + (speed / distance.max(1.0));
 
                 if new_progress >= 1.0 {
                     // Ship arrived
                     ship.set_location(ShipLocation::AtPlanet(to));
+                    events.push(TurnEvent::ShipArrived {
+                        ship: ship_id,
+                        race: ship.owner(),
+                        planet: to,
+                    });
 
-                    // Check if planet is uninhabited and colonize it
-                    if let Some(planet) = self.galaxy.get_planet_mut(to)
-                        && planet.owner().is_none()
-                    {
-                        planet.set_owner(Some(ship.owner().0));
+                    // Arriving explores the planet; settling it takes colonists
+                    if let Some(planet) = self.galaxy.get_planet_mut(to) {
+                        planet.explore(ship.owner().0);
                     }
                 } else {
                     // Continue traveling
@@ -331,133 +732,133 @@ impl GameState {
                 }
             }
         }
+
+        self.pending_events.extend(events);
     }
 
+    /// Fight one battle at every planet where races at war meet
     fn process_combat(&mut self) {
-        // Find ships at the same planet that should fight
-        let mut combat_pairs: Vec<(ShipId, ShipId)> = Vec::new();
-
-        // Group ships by planet
-        let mut ships_at_planets: HashMap<PlanetId, Vec<(ShipId, RaceId)>> = HashMap::new();
+        self.battles.clear();
 
-        for (ship_id, ship) in &self.ships {
+        let mut races_at_planets: BTreeMap<PlanetId, BTreeSet<RaceId>> = BTreeMap::new();
+        for ship in self.ships.values() {
             if let ShipLocation::AtPlanet(planet_id) = ship.location() {
-                ships_at_planets
+                races_at_planets
                     .entry(*planet_id)
                     .or_default()
-                    .push((*ship_id, ship.owner()));
+                    .insert(ship.owner());
             }
         }
 
-        // Find hostile pairs
-        for ships in ships_at_planets.values() {
-            for i in 0..ships.len() {
-                for j in (i + 1)..ships.len() {
-                    let (ship1_id, race1) = ships[i];
-                    let (ship2_id, race2) = ships[j];
-
-                    if self.diplomacy.should_attack(race1, race2) {
-                        combat_pairs.push((ship1_id, ship2_id));
-                        // Mark races as hostile if they weren't already
-                        self.diplomacy.make_hostile(race1, race2);
-                    }
-                }
+        for (planet, mut races) in races_at_planets {
+            // The owner's defences fight even when none of its ships are there
+            let defences = self.planet_defences(planet);
+            if let Some(defences) = &defences {
+                races.insert(defences.owner);
             }
-        }
-
-        // Process combat
-        let mut ships_to_remove = Vec::new();
-
-        for (ship1_id, ship2_id) in combat_pairs {
-            // Skip if either ship was already destroyed in a previous combat
-            if !self.ships.contains_key(&ship1_id) || !self.ships.contains_key(&ship2_id) {
+            let at_war = races.iter().any(|race1| {
+                races
+                    .iter()
+                    .any(|race2| self.diplomacy.should_attack(*race1, *race2))
+            });
+            if !at_war {
                 continue;
             }
 
-            // Remove both ships temporarily to get mutable access
-            let mut ship1 = self.ships.remove(&ship1_id).unwrap();
-            let mut ship2 = self.ships.remove(&ship2_id).unwrap();
-
-            // Get technology for both races
-            let default_tech = crate::race::Technology::new();
-            let ship1_tech = self
-                .races
-                .get(&ship1.owner())
-                .map_or(&default_tech, |r| r.technology());
-
-            let ship2_tech = self
-                .races
-                .get(&ship2.owner())
-                .map_or(&default_tech, |r| r.technology());
-
-            // Resolve combat
-            let result =
-                CombatSystem::resolve_combat(&mut ship1, ship1_tech, &mut ship2, ship2_tech);
-
-            // Put survivors back
-            if result.attacker_survived {
-                self.ships.insert(ship1_id, ship1);
-            } else {
-                ships_to_remove.push(ship1_id);
-            }
+            let diplomacy = &self.diplomacy;
+            let mut ships: Vec<&mut Ship> = self
+                .ships
+                .values_mut()
+                .filter(|ship| *ship.location() == ShipLocation::AtPlanet(planet))
+                .collect();
+            let report = CombatSystem::resolve_battle(
+                planet,
+                &mut ships,
+                defences.as_ref(),
+                |race1, race2| diplomacy.should_attack(race1, race2),
+                &mut self.rng,
+            );
 
-            if result.defender_survived {
-                self.ships.insert(ship2_id, ship2);
-            } else {
-                ships_to_remove.push(ship2_id);
+            for ship in &report.ships_lost {
+                self.ships.remove(ship);
+            }
+            if report.defences_destroyed
+                && let Some(planet) = self.galaxy.get_planet_mut(planet)
+            {
+                planet.destroy_defences();
             }
+            self.record(TurnEvent::BattleFought {
+                planet,
+                races: report.races.clone(),
+                ships_lost: report.ships_lost.clone(),
+            });
+            self.battles.push(report);
         }
     }
 
-    /// Process planet bombing and capture
-    /// Ships at enemy planets bomb them (reduce pop/industry by 75%)
-    /// Planet ownership changes based on remaining ships:
-    /// - Only one race has ships -> that race captures planet
-    /// - Multiple races or no ships -> planet becomes unowned
-    fn process_planet_bombing(&mut self) {
-        use std::collections::HashMap;
-        use std::collections::HashSet;
+    /// The defences of an owned planet, fighting with its owner's tech
+    fn planet_defences(&self, planet: PlanetId) -> Option<PlanetDefences> {
+        let planet = self.galaxy.get_planet(planet)?;
+        let design = planet.defences()?;
+        let owner = RaceId(planet.owner()?);
+        let tech = self.races.get(&owner)?.technology().clone();
+        Some(PlanetDefences {
+            owner,
+            design,
+            tech,
+        })
+    }
 
-        // Group ships by planet location
-        let mut ships_at_planets: HashMap<PlanetId, Vec<RaceId>> = HashMap::new();
+    /// Process planet bombing
+    ///
+    /// Armed ships that survived the battle at a planet owned by a race they
+    /// are hostile to bomb it, once its defences are down: population and
+    /// industry drop by 75% and the planet is left unowned. As in GalaxyNG,
+    /// the attackers then capture it by landing colonists.
+    fn process_planet_bombing(&mut self) {
+        let mut attackers_at_planets: BTreeMap<PlanetId, BTreeSet<RaceId>> = BTreeMap::new();
         for ship in self.ships.values() {
-            if let ShipLocation::AtPlanet(planet_id) = ship.location() {
-                ships_at_planets
+            if let ShipLocation::AtPlanet(planet_id) = ship.location()
+                && ship.attack_strength() > 0.0
+            {
+                attackers_at_planets
                     .entry(*planet_id)
                     .or_default()
-                    .push(ship.owner());
+                    .insert(ship.owner());
             }
         }
 
-        // Process each planet with ships
-        for (planet_id, ship_owners) in ships_at_planets {
-            let Some(planet) = self.galaxy.get_planet_mut(planet_id) else {
+        let mut events = Vec::new();
+        for (planet_id, races) in attackers_at_planets {
+            let Some(owner) = self
+                .galaxy
+                .get_planet(planet_id)
+                .and_then(|planet| planet.owner())
+                .map(RaceId)
+            else {
                 continue;
             };
-
-            // Get unique races at this planet
-            let unique_races: HashSet<RaceId> = ship_owners.into_iter().collect();
-
-            // If planet is owned and has enemy ships, bomb it
-            if let Some(planet_owner) = planet.owner() {
-                let has_enemy_ships = unique_races.iter().any(|race| race.0 != planet_owner);
-
-                if has_enemy_ships {
-                    planet.bomb();
-                }
+            let attackers: Vec<RaceId> = races
+                .into_iter()
+                .filter(|race| self.diplomacy.should_attack(*race, owner))
+                .collect();
+            if attackers.is_empty() || self.planet_defences(planet_id).is_some() {
+                continue;
             }
 
-            // Determine new ownership:
-            // - If exactly one race has ships -> they capture planet
-            // - If multiple races or no ships -> unowned
-            let new_owner = if unique_races.len() == 1 {
-                Some(unique_races.iter().next().unwrap().0)
-            } else {
-                None
+            let Some(planet) = self.galaxy.get_planet_mut(planet_id) else {
+                continue;
             };
-
-            planet.set_owner(new_owner);
+            planet.bomb();
+            planet.set_owner(None);
+            events.push(TurnEvent::PlanetBombed {
+                planet: planet_id,
+                owner,
+                attackers,
+            });
         }
+
+        self.pending_events.extend(events);
     }
 
     /// Execute racebot decisions
@@ -466,30 +867,83 @@ impl GameState {
         race_id: RaceId,
         decisions: crate::racebot::RacebotDecisions,
     ) {
+        let mut rejected = Vec::new();
+
         // Apply production orders
         for (planet_id, production_type) in decisions.production_orders {
-            if let Some(planet) = self.galaxy.get_planet_mut(planet_id)
-                && planet.owner() == Some(race_id.0)
-            {
-                planet.set_production_type(production_type);
+            match self.galaxy.get_planet_mut(planet_id) {
+                Some(planet) if planet.owner() == Some(race_id.0) => {
+                    planet.set_production_type(production_type);
+                }
+                Some(_) => rejected.push(GameError::NotPlanetOwner {
+                    planet: planet_id,
+                    race: race_id,
+                }),
+                None => rejected.push(GameError::UnknownPlanet(planet_id)),
             }
         }
 
-        // Move ships
-        for ship_movement in decisions.ship_movements {
-            println!(
-                "    Ordering {} to travel to {:?}",
-                ship_movement.ship_id, ship_movement.destination
-            );
-            self.order_ship_travel(ship_movement.ship_id, ship_movement.destination);
+        // Load and unload cargo before ships leave
+        for cargo_order in decisions.cargo_orders {
+            let result = match cargo_order {
+                CargoOrder::Load { ship_id, cargo } => self.load_ship(ship_id, cargo, None),
+                CargoOrder::Unload { ship_id, cargo } => self.unload_ship(ship_id, cargo, None),
+            };
+            if let Err(err) = result {
+                rejected.push(err);
+            }
         }
 
-        // Build ships
-        if !decisions.ship_builds.is_empty() {
-            println!("    Building {} ships", decisions.ship_builds.len());
+        // Move ships, keeping those that leave together in one fleet
+        for fleet_movement in decisions.fleet_movements {
+            if let Err(err) =
+                self.send_as_fleet(race_id, &fleet_movement.ships, fleet_movement.destination)
+            {
+                rejected.push(err);
+            }
+        }
+        for ship_movement in decisions.ship_movements {
+            if let Err(err) =
+                self.order_ship_travel(ship_movement.ship_id, ship_movement.destination)
+            {
+                rejected.push(err);
+            }
         }
+
+        // Build ships, registering the racebot's designs as ship types
         for ship_build in decisions.ship_builds {
-            self.build_ship(ship_build.planet_id, ship_build.design);
+            let built = self
+                .racebot_ship_type(race_id, ship_build.name, ship_build.design)
+                .and_then(|ship_type| self.build_ship_of_type(ship_build.planet_id, ship_type));
+            if let Err(err) = built {
+                rejected.push(err);
+            }
+        }
+
+        for error in rejected {
+            self.record(TurnEvent::OrderRejected {
+                race: race_id,
+                error,
+            });
+        }
+    }
+
+    /// The racebot's ship type with this name, designing it on first use
+    fn racebot_ship_type(
+        &mut self,
+        race_id: RaceId,
+        name: String,
+        design: ShipDesign,
+    ) -> Result<ShipTypeId, GameError> {
+        design.validate()?;
+        let race = self
+            .races
+            .get_mut(&race_id)
+            .ok_or(GameError::UnknownRace(race_id))?;
+        match race.ship_type_by_name(&name) {
+            Some(id) if race.ship_type(id).map(|t| *t.design()) == Some(design) => Ok(id),
+            Some(_) => Err(GameError::DuplicateShipType(name)),
+            None => race.add_ship_type(name, design),
         }
     }
 
@@ -508,8 +962,6 @@ impl GameState {
             None => return,
         };
 
-        println!("  {} ({:?}) making decisions...", race.name(), personality);
-
         // Create racebot with appropriate personality
         let racebot = Racebot::with_personality(race_id, personality);
 
@@ -540,7 +992,11 @@ impl GameState {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::combat::Combatant;
+    use crate::planet::DEFENCE_REBUILD_PER_TURN;
     use crate::planet::Position;
+    use crate::race::RESEARCH_PER_LEVEL;
+    use crate::ship::DesignError;
     use crate::ship::Ship;
     use crate::ship::ShipDesign;
     use crate::ship::ShipId;
@@ -561,6 +1017,7 @@ mod tests {
         let design = ShipDesign::new(1.0, 1, 2.0, 2.0, 0.0);
         let ship = Ship::new(ShipId(1), RaceId(1), design, planet_id);
         game.ships.insert(ShipId(1), ship);
+        game.set_relationship(RaceId(0), RaceId(1), Relationship::Hostile);
 
         // Process bombing
         game.process_planet_bombing();
@@ -573,7 +1030,7 @@ mod tests {
     }
 
     #[test]
-    fn test_planet_capture_single_race() {
+    fn test_planet_capture_needs_colonists() {
         let mut game = GameState::new(1000.0, 1000.0);
 
         // Create a planet owned by race 0
@@ -581,18 +1038,106 @@ mod tests {
             .galaxy
             .add_planet(Position::new(100.0, 100.0), 100, Some(0));
 
-        // Add race 1 ship at the planet
+        // Add a race 1 warship and a freighter with colonists at the planet
         let design = ShipDesign::new(1.0, 1, 2.0, 2.0, 0.0);
         let ship = Ship::new(ShipId(1), RaceId(1), design, planet_id);
         game.ships.insert(ShipId(1), ship);
+        let freighter = ShipDesign::new(1.0, 0, 0.0, 0.0, 2.0);
+        let mut ship = Ship::new(ShipId(2), RaceId(1), freighter, planet_id);
+        ship.load_cargo(CargoType::Colonists, 1.0, 1.0).unwrap();
+        game.ships.insert(ShipId(2), ship);
+        game.set_relationship(RaceId(0), RaceId(1), Relationship::Hostile);
+
+        // Colonists cannot land while race 0 still holds the planet
+        assert_eq!(
+            game.unload_ship(ShipId(2), CargoType::Colonists, None),
+            Err(GameError::NotPlanetOwner {
+                planet: planet_id,
+                race: RaceId(1),
+            })
+        );
 
-        // Process bombing
+        // Bombing leaves the planet unowned rather than captured
         game.process_planet_bombing();
+        assert_eq!(game.galaxy.get_planet(planet_id).unwrap().owner(), None);
 
+        // Landing colonists takes it, joining the survivors
+        assert_eq!(
+            game.unload_ship(ShipId(2), CargoType::Colonists, None),
+            Ok(1.0)
+        );
         let planet = game.galaxy.get_planet(planet_id).unwrap();
+        assert_eq!(planet.owner(), Some(1));
+        assert_eq!(planet.population(), 25.0 + 8.0);
+    }
 
-        // Planet should be captured by race 1
+    #[test]
+    fn test_captured_shipyard_loses_its_production() {
+        let mut game = GameState::new(1000.0, 1000.0);
+        let planet_id = game
+            .galaxy
+            .add_planet(Position::new(100.0, 100.0), 100, Some(0));
+
+        // Race 0 has a ship half built and some production already used
+        let planet = game.galaxy.get_planet_mut(planet_id).unwrap();
+        planet.set_production_type(ProductionType::Ships(ShipTypeId(0)));
+        planet.produce_ships(&ShipDesign::new(50.0, 0, 0.0, 0.0, 0.0));
+        planet.spend_production(10.0).unwrap();
+        assert!(planet.ship_progress() > 0.0);
+
+        let design = ShipDesign::new(1.0, 1, 2.0, 2.0, 0.0);
+        let ship = Ship::new(ShipId(1), RaceId(1), design, planet_id);
+        game.ships.insert(ShipId(1), ship);
+        let freighter = ShipDesign::new(1.0, 0, 0.0, 0.0, 2.0);
+        let mut ship = Ship::new(ShipId(2), RaceId(1), freighter, planet_id);
+        ship.load_cargo(CargoType::Colonists, 1.0, 1.0).unwrap();
+        game.ships.insert(ShipId(2), ship);
+        game.set_relationship(RaceId(0), RaceId(1), Relationship::Hostile);
+
+        game.process_planet_bombing();
+        // The bombed planet stops building as soon as it is lost
+        let planet = game.galaxy.get_planet(planet_id).unwrap();
+        assert_eq!(planet.owner(), None);
+        assert_eq!(planet.production_type(), ProductionType::None);
+        assert_eq!(planet.ship_progress(), 0.0);
+        game.unload_ship(ShipId(2), CargoType::Colonists, None)
+            .unwrap();
+
+        // The new owner does not inherit race 0's shipyard
+        let planet = game.galaxy.get_planet(planet_id).unwrap();
         assert_eq!(planet.owner(), Some(1));
+        assert_eq!(planet.production_type(), ProductionType::None);
+        assert_eq!(planet.ship_progress(), 0.0);
+        assert_eq!(planet.available_production(), planet.production());
+    }
+
+    #[test]
+    fn test_only_armed_hostile_ships_bomb() {
+        let mut game = GameState::new(1000.0, 1000.0);
+        let planet_id = game
+            .galaxy
+            .add_planet(Position::new(100.0, 100.0), 100, Some(0));
+        let warship = ShipDesign::new(1.0, 1, 2.0, 2.0, 0.0);
+        let scout = ShipDesign::new(1.0, 0, 0.0, 0.0, 0.0);
+        // An unarmed hostile scout, a friendly and a neutral warship
+        for (id, race, design) in [(1, 1, scout), (2, 2, warship), (3, 3, warship)] {
+            let ship = Ship::new(ShipId(id), RaceId(race), design, planet_id);
+            game.ships.insert(ShipId(id), ship);
+        }
+        game.set_relationship(RaceId(0), RaceId(1), Relationship::Hostile);
+        game.set_relationship(RaceId(0), RaceId(2), Relationship::Friendly);
+
+        game.process_planet_bombing();
+
+        let planet = game.galaxy.get_planet(planet_id).unwrap();
+        assert_eq!(planet.owner(), Some(0));
+        assert_eq!(planet.population(), 100.0);
+        assert!(
+            !game
+                .pending_events
+                .iter()
+                .any(|event| matches!(event, TurnEvent::PlanetBombed { .. }))
+        );
     }
 
     #[test]
@@ -610,16 +1155,787 @@ mod tests {
         let ship2 = Ship::new(ShipId(2), RaceId(2), design, planet_id);
         game.ships.insert(ShipId(1), ship1);
         game.ships.insert(ShipId(2), ship2);
+        game.set_relationship(RaceId(0), RaceId(1), Relationship::Hostile);
+        game.set_relationship(RaceId(0), RaceId(2), Relationship::Hostile);
 
         // Process bombing
         game.process_planet_bombing();
 
         let planet = game.galaxy.get_planet(planet_id).unwrap();
 
-        // Planet should be unowned due to multiple races
+        // Planet is left unowned for either race to settle
         assert!(planet.owner().is_none());
     }
 
+    #[test]
+    fn test_bombing_and_capture_events() {
+        let mut game = GameState::new(1000.0, 1000.0);
+        let planet_id = game
+            .galaxy
+            .add_planet(Position::new(100.0, 100.0), 100, Some(0));
+
+        let design = ShipDesign::new(1.0, 1, 2.0, 2.0, 0.0);
+        let ship = Ship::new(ShipId(1), RaceId(1), design, planet_id);
+        game.ships.insert(ShipId(1), ship);
+        let freighter = ShipDesign::new(1.0, 0, 0.0, 0.0, 2.0);
+        let mut ship = Ship::new(ShipId(2), RaceId(1), freighter, planet_id);
+        ship.load_cargo(CargoType::Colonists, 1.0, 1.0).unwrap();
+        game.ships.insert(ShipId(2), ship);
+        game.set_relationship(RaceId(0), RaceId(1), Relationship::Hostile);
+        game.pending_events.clear();
+
+        game.process_planet_bombing();
+        assert_eq!(
+            game.pending_events,
+            vec![TurnEvent::PlanetBombed {
+                planet: planet_id,
+                owner: RaceId(0),
+                attackers: vec![RaceId(1)],
+            }]
+        );
+
+        // The planet changes hands when the colonists land
+        game.pending_events.clear();
+        game.unload_ship(ShipId(2), CargoType::Colonists, None)
+            .unwrap();
+        assert_eq!(
+            game.pending_events,
+            vec![TurnEvent::PlanetCaptured {
+                planet: planet_id,
+                previous_owner: RaceId(0),
+                new_owner: RaceId(1),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_one_battle_per_planet() {
+        let mut game = GameState::new(1000.0, 1000.0);
+        let planet_id = game
+            .galaxy
+            .add_planet(Position::new(100.0, 100.0), 100, None);
+        let warship = ShipDesign::new(1.0, 1, 4.0, 4.0, 0.0);
+        for (id, race) in [(0, 0), (1, 0), (2, 1), (3, 2)] {
+            let ship = Ship::new(ShipId(id), RaceId(race), warship, planet_id);
+            game.ships.insert(ShipId(id), ship);
+        }
+        game.set_relationship(RaceId(0), RaceId(1), Relationship::Hostile);
+        game.set_relationship(RaceId(0), RaceId(2), Relationship::Hostile);
+
+        game.process_combat();
+
+        assert_eq!(game.battles().len(), 1);
+        let report = &game.battles()[0];
+        assert_eq!(report.planet, planet_id);
+        assert_eq!(report.races, vec![RaceId(0), RaceId(1), RaceId(2)]);
+        // Races 1 and 2 are not at war with each other
+        for shot in &report.shots {
+            let owner = |combatant| match combatant {
+                Combatant::Ship(id) => [0, 0, 1, 2][id.0 as usize],
+                Combatant::Planet(_) => unreachable!("the planet is unowned"),
+            };
+            assert!(owner(shot.attacker) == 0 || owner(shot.target) == 0);
+        }
+        for ship in &report.ships_lost {
+            assert!(game.get_ship(*ship).is_none());
+        }
+        assert!(game.pending_events.contains(&TurnEvent::BattleFought {
+            planet: planet_id,
+            races: report.races.clone(),
+            ships_lost: report.ships_lost.clone(),
+        }));
+    }
+
+    #[test]
+    fn test_planet_defences_join_battles() {
+        let mut game = GameState::new(1000.0, 1000.0);
+        let home = game
+            .galaxy
+            .add_planet(Position::new(100.0, 100.0), 100, Some(0));
+        game.add_race("Defenders".to_string(), home.0);
+        let scout = ShipDesign::new(1.0, 0, 0.0, 0.0, 0.0);
+        game.ships
+            .insert(ShipId(1), Ship::new(ShipId(1), RaceId(1), scout, home));
+
+        // Neutral visitors are left alone
+        game.process_combat();
+        assert!(game.battles().is_empty());
+        assert!(game.get_ship(ShipId(1)).is_some());
+
+        game.set_relationship(RaceId(0), RaceId(1), Relationship::Hostile);
+        game.process_combat();
+
+        let report = &game.battles()[0];
+        assert_eq!(report.races, vec![RaceId(0), RaceId(1)]);
+        assert_eq!(report.shots[0].attacker, Combatant::Planet(home));
+        assert!(!report.defences_destroyed);
+        assert_eq!(report.ships_lost, vec![ShipId(1)]);
+        assert!(game.get_ship(ShipId(1)).is_none());
+
+        // With the scout gone there is nobody left to bomb the planet
+        game.process_planet_bombing();
+        assert_eq!(game.galaxy.get_planet(home).unwrap().owner(), Some(0));
+    }
+
+    #[test]
+    fn test_bombing_waits_for_defences_to_fall() {
+        let mut game = GameState::new(1000.0, 1000.0);
+        let home = game
+            .galaxy
+            .add_planet(Position::new(100.0, 100.0), 100, Some(0));
+        game.add_race("Defenders".to_string(), home.0);
+        // Out-guns the planet's defences and shrugs off their fire
+        let dreadnought = ShipDesign::new(1.0, 1, 200.0, 200.0, 0.0);
+        game.ships.insert(
+            ShipId(1),
+            Ship::new(ShipId(1), RaceId(1), dreadnought, home),
+        );
+        game.set_relationship(RaceId(0), RaceId(1), Relationship::Hostile);
+
+        game.process_planet_bombing();
+        assert_eq!(game.galaxy.get_planet(home).unwrap().owner(), Some(0));
+
+        game.process_combat();
+        assert!(game.battles()[0].defences_destroyed);
+        assert!(game.battles()[0].ships_lost.is_empty());
+        game.process_planet_bombing();
+        assert_eq!(game.galaxy.get_planet(home).unwrap().owner(), None);
+    }
+
+    #[test]
+    fn test_destroyed_defences_stay_down_the_next_turn() {
+        let mut game = GameState::new(1000.0, 1000.0);
+        let home = game
+            .galaxy
+            .add_planet(Position::new(100.0, 100.0), 100, Some(0));
+        game.add_race("Defenders".to_string(), home.0);
+        let full = game.galaxy.get_planet(home).unwrap().defences().unwrap();
+        let dreadnought = ShipDesign::new(1.0, 1, 200.0, 200.0, 0.0);
+        game.ships.insert(
+            ShipId(1),
+            Ship::new(ShipId(1), RaceId(1), dreadnought, home),
+        );
+        game.set_relationship(RaceId(0), RaceId(1), Relationship::Hostile);
+        game.process_combat();
+        assert!(game.battles()[0].defences_destroyed);
+        // The attacker leaves before it can bomb the planet
+        game.ships.remove(&ShipId(1));
+
+        game.advance_turn();
+
+        // Only a quarter of the defences is back a turn later
+        let planet = game.galaxy.get_planet(home).unwrap();
+        assert_eq!(planet.owner(), Some(0));
+        assert_eq!(planet.defence_strength(), DEFENCE_REBUILD_PER_TURN);
+        assert!(planet.defences().unwrap().weapons_mass() < full.weapons_mass());
+    }
+
+    #[test]
+    fn test_advance_turn_reports_orders_and_turn_events() {
+        let mut game = GameState::new(1000.0, 1000.0);
+        let home = game
+            .galaxy
+            .add_planet(Position::new(100.0, 100.0), 100, Some(0));
+        let target = game
+            .galaxy
+            .add_planet(Position::new(105.0, 100.0), 50, None);
+        let race = game.add_race("Humans".to_string(), home.0);
+
+        game.galaxy
+            .get_planet_mut(home)
+            .unwrap()
+            .add_materials(100.0);
+        let ship = game
+            .build_ship(home, ShipDesign::new(1.0, 0, 0.0, 0.0, 0.0))
+            .unwrap();
+        game.order_ship_travel(ship, target).unwrap();
+
+        // The build happened between turns and is reported with the turn
+        let events = game.advance_turn().to_vec();
+        assert_eq!(
+            events,
+            vec![
+                TurnEvent::ShipBuilt {
+                    ship,
+                    race,
+                    planet: home,
+                },
+                TurnEvent::ShipArrived {
+                    ship,
+                    race,
+                    planet: target,
+                },
+            ]
+        );
+        assert_eq!(game.events(), events.as_slice());
+        // An empty ship only explores the planet
+        let target = game.galaxy.get_planet(target).unwrap();
+        assert_eq!(target.owner(), None);
+        assert!(target.is_explored_by(race.0));
+
+        // Nothing happens on the next turn
+        assert!(game.advance_turn().is_empty());
+    }
+
+    #[test]
+    fn test_tech_advance_event() {
+        let mut game = GameState::new(1000.0, 1000.0);
+        // A fully developed size 5000 planet produces one tech level per turn
+        let home = game
+            .galaxy
+            .add_planet(Position::new(100.0, 100.0), 5000, Some(0));
+        let race = game.add_race("Humans".to_string(), home.0);
+        game.galaxy
+            .get_planet_mut(home)
+            .unwrap()
+            .set_production_type(ProductionType::Research(TechnologyType::Weapon));
+
+        let events = game.advance_turn();
+
+        assert!(events.contains(&TurnEvent::TechAdvanced {
+            race,
+            tech: TechnologyType::Weapon,
+            level: 2,
+        }));
+    }
+
+    #[test]
+    fn test_research_production_carries_over() {
+        let mut game = GameState::new(1000.0, 1000.0);
+        let home = game
+            .galaxy
+            .add_planet(Position::new(100.0, 100.0), 1000, Some(0));
+        let race = game.add_race("Humans".to_string(), home.0);
+        game.galaxy
+            .get_planet_mut(home)
+            .unwrap()
+            .set_production_type(ProductionType::Research(TechnologyType::Drive));
+
+        // 1000 production per turn, 5000 per level
+        for _ in 0..4 {
+            game.advance_turn();
+        }
+        let humans = game.get_race(race).unwrap();
+        assert_eq!(humans.technology().drive_level(), 1);
+        assert_eq!(humans.research_progress(TechnologyType::Drive), 4000.0);
+
+        game.advance_turn();
+        let humans = game.get_race(race).unwrap();
+        assert_eq!(humans.technology().drive_level(), 2);
+        assert_eq!(humans.research_progress(TechnologyType::Drive), 0.0);
+
+        // Excess effort carries over, possibly across several levels
+        let humans = game.get_race_mut(race).unwrap();
+        assert!(humans.add_research(TechnologyType::Drive, 2.5 * RESEARCH_PER_LEVEL));
+        assert_eq!(humans.technology().drive_level(), 4);
+        assert_eq!(
+            humans.research_progress(TechnologyType::Drive),
+            0.5 * RESEARCH_PER_LEVEL
+        );
+    }
+
+    #[test]
+    fn test_ship_production_launches_ships() {
+        let mut game = GameState::new(1000.0, 1000.0);
+        let home = game
+            .galaxy
+            .add_planet(Position::new(100.0, 100.0), 100, Some(0));
+        let race = game.add_race("Humans".to_string(), home.0);
+        let cruiser = game
+            .get_race_mut(race)
+            .unwrap()
+            .add_ship_type(
+                "Cruiser".to_string(),
+                ShipDesign::new(5.0, 1, 3.0, 2.0, 0.0),
+            )
+            .unwrap();
+        game.galaxy
+            .get_planet_mut(home)
+            .unwrap()
+            .set_production_type(ProductionType::Ships(cruiser));
+
+        // Mass 10 costs 101 production with no materials stockpiled
+        assert!(game.advance_turn().is_empty());
+        assert_eq!(game.ships().count(), 0);
+
+        let events = game.advance_turn().to_vec();
+        let ship = game.ships().next().unwrap();
+        assert_eq!(ship.owner(), race);
+        assert_eq!(ship.location(), &ShipLocation::AtPlanet(home));
+        assert_eq!(ship.design(), &ShipDesign::new(5.0, 1, 3.0, 2.0, 0.0));
+        assert_eq!(
+            events,
+            vec![TurnEvent::ShipBuilt {
+                ship: ship.id(),
+                race,
+                planet: home,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_delete_ship_type_only_when_unused() {
+        let mut game = GameState::new(1000.0, 1000.0);
+        let home = game
+            .galaxy
+            .add_planet(Position::new(100.0, 100.0), 100, Some(0));
+        let race = game.add_race("Humans".to_string(), home.0);
+        game.galaxy
+            .get_planet_mut(home)
+            .unwrap()
+            .add_materials(100.0);
+        let humans = game.get_race_mut(race).unwrap();
+        let scout = humans
+            .add_ship_type("Scout".to_string(), ShipDesign::new(1.0, 0, 0.0, 0.0, 0.0))
+            .unwrap();
+        let drone = humans
+            .add_ship_type("Drone".to_string(), ShipDesign::new(1.0, 0, 0.0, 1.0, 0.0))
+            .unwrap();
+        let freighter = humans
+            .add_ship_type(
+                "Freighter".to_string(),
+                ShipDesign::new(1.0, 0, 0.0, 0.0, 2.0),
+            )
+            .unwrap();
+
+        let ship = game.build_ship_of_type(home, scout).unwrap();
+        assert_eq!(game.get_ship(ship).unwrap().ship_type(), Some(scout));
+        game.galaxy
+            .get_planet_mut(home)
+            .unwrap()
+            .set_production_type(ProductionType::Ships(drone));
+
+        assert_eq!(
+            game.delete_ship_type(race, "scout"),
+            Err(GameError::ShipTypeInUse("scout".to_string()))
+        );
+        assert_eq!(
+            game.delete_ship_type(race, "Drone"),
+            Err(GameError::ShipTypeInUse("Drone".to_string()))
+        );
+        assert_eq!(
+            game.delete_ship_type(race, "Battleship"),
+            Err(GameError::UnknownShipType("Battleship".to_string()))
+        );
+        game.delete_ship_type(race, "Freighter").unwrap();
+
+        let names: Vec<&str> = game
+            .get_race(race)
+            .unwrap()
+            .ship_types()
+            .map(|(_, t)| t.name())
+            .collect();
+        assert_eq!(names, vec!["Scout", "Drone"]);
+        assert!(game.get_race(race).unwrap().ship_type(freighter).is_none());
+    }
+
+    #[test]
+    fn test_racebot_builds_named_ship_types() {
+        let mut game = GameState::new(1000.0, 1000.0);
+        let home = game
+            .galaxy
+            .add_planet(Position::new(100.0, 100.0), 100, Some(0));
+        let race = game.add_race("Humans".to_string(), home.0);
+        game.galaxy
+            .get_planet_mut(home)
+            .unwrap()
+            .add_materials(100.0);
+        let design = ShipDesign::new(1.0, 0, 0.0, 1.0, 0.0);
+
+        let decisions = || crate::racebot::RacebotDecisions {
+            ship_builds: vec![crate::racebot::ShipBuild {
+                planet_id: home,
+                design,
+                name: "Picket".to_string(),
+            }],
+            ..Default::default()
+        };
+        game.execute_racebot_decisions(race, decisions());
+        game.execute_racebot_decisions(race, decisions());
+
+        let humans = game.get_race(race).unwrap();
+        let picket = humans.ship_type_by_name("Picket").unwrap();
+        assert_eq!(humans.ship_types().count(), 1);
+        assert_eq!(humans.ship_type(picket).unwrap().design(), &design);
+        assert!(game.ships().all(|s| s.ship_type() == Some(picket)));
+        assert_eq!(game.ships().count(), 2);
+    }
+
+    #[test]
+    fn test_racebot_invalid_design_rejected() {
+        let mut game = GameState::new(1000.0, 1000.0);
+        let home = game
+            .galaxy
+            .add_planet(Position::new(100.0, 100.0), 100, Some(0));
+        let race = game.add_race("Humans".to_string(), home.0);
+        game.galaxy
+            .get_planet_mut(home)
+            .unwrap()
+            .add_materials(100.0);
+
+        game.execute_racebot_decisions(
+            race,
+            crate::racebot::RacebotDecisions {
+                ship_builds: vec![crate::racebot::ShipBuild {
+                    planet_id: home,
+                    design: ShipDesign::new(0.5, 0, 0.0, 0.0, 0.0),
+                    name: "Dart".to_string(),
+                }],
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(game.ships().count(), 0);
+        assert!(
+            game.get_race(race)
+                .unwrap()
+                .ship_type_by_name("Dart")
+                .is_none()
+        );
+        assert!(game.pending_events.iter().any(|e| matches!(
+            e,
+            TurnEvent::OrderRejected {
+                error: GameError::InvalidDesign(DesignError::InvalidMass { .. }),
+                ..
+            }
+        )));
+    }
+
+    #[test]
+    fn test_ships_keep_their_build_tech() {
+        let mut game = GameState::new(1000.0, 1000.0);
+        let home = game
+            .galaxy
+            .add_planet(Position::new(100.0, 100.0), 100, Some(0));
+        let race = game.add_race("Humans".to_string(), home.0);
+        game.galaxy
+            .get_planet_mut(home)
+            .unwrap()
+            .add_materials(100.0);
+        let design = ShipDesign::new(10.0, 1, 10.0, 10.0, 0.0);
+        let old = game.build_ship(home, design).unwrap();
+        game.get_race_mut(race)
+            .unwrap()
+            .add_research(TechnologyType::Weapon, RESEARCH_PER_LEVEL);
+        let new = game.build_ship(home, design).unwrap();
+
+        let old_attack = game.get_ship(old).unwrap().attack_strength();
+        assert_eq!(
+            game.get_ship(new).unwrap().attack_strength(),
+            2.0 * old_attack
+        );
+
+        // 10 × 10 × (1 − 1/2) production from the planet
+        assert_eq!(game.upgrade_ship(old), Ok(50.0));
+        assert_eq!(
+            game.get_ship(old).unwrap().attack_strength(),
+            2.0 * old_attack
+        );
+        assert_eq!(game.upgrade_ship(old), Ok(0.0));
+
+        game.get_race_mut(race)
+            .unwrap()
+            .add_research(TechnologyType::Shield, 20.0 * RESEARCH_PER_LEVEL);
+        assert!(matches!(
+            game.upgrade_ship(new),
+            Err(GameError::InsufficientProduction { .. })
+        ));
+
+        assert_eq!(
+            game.upgrade_ship(ShipId(99)),
+            Err(GameError::UnknownShip(ShipId(99)))
+        );
+        let stray = Ship::new(ShipId(99), RaceId(7), design, home);
+        game.ships.insert(ShipId(99), stray);
+        assert_eq!(
+            game.upgrade_ship(ShipId(99)),
+            Err(GameError::UnknownRace(RaceId(7)))
+        );
+    }
+
+    #[test]
+    fn test_scrap_ship_recovers_materials_and_cargo() {
+        let mut game = GameState::new(1000.0, 1000.0);
+        let home = game
+            .galaxy
+            .add_planet(Position::new(100.0, 100.0), 100, Some(0));
+        let target = game
+            .galaxy
+            .add_planet(Position::new(105.0, 100.0), 50, None);
+        let race = game.add_race("Humans".to_string(), home.0);
+        let planet = game.galaxy.get_planet_mut(home).unwrap();
+        planet.add_materials(100.0);
+        planet.add_colonists(1.0);
+        let ship = game
+            .build_ship(home, ShipDesign::new(5.0, 0, 0.0, 0.0, 5.0))
+            .unwrap();
+        game.load_ship(ship, CargoType::Colonists, None).unwrap();
+        game.load_ship(ship, CargoType::Materials, Some(2.0))
+            .unwrap();
+        let other = game
+            .build_ship(home, ShipDesign::new(1.0, 0, 0.0, 0.0, 0.0))
+            .unwrap();
+        game.merge_ships(race, &[other]).unwrap();
+        game.order_ship_travel(other, target).unwrap();
+        game.advance_turn();
+        let planet = game.galaxy.get_planet(home).unwrap();
+        let (materials, colonists) = (planet.materials(), planet.colonists());
+
+        assert_eq!(
+            game.scrap_ship(other),
+            Err(GameError::NotPlanetOwner {
+                planet: target,
+                race,
+            })
+        );
+        // Half of the 10 materials the ship cost, plus the 2 on board
+        assert_eq!(game.scrap_ship(ship), Ok(5.0));
+        assert!(game.get_ship(ship).is_none());
+        let planet = game.galaxy.get_planet(home).unwrap();
+        assert_eq!(planet.materials(), materials + 7.0);
+        assert_eq!(planet.colonists(), colonists + 1.0);
+        assert_eq!(game.scrap_ship(ship), Err(GameError::UnknownShip(ship)));
+
+        // Scrapped ships leave their groups
+        game.order_ship_travel(other, home).unwrap();
+        while game.get_ship(other).unwrap().location().is_traveling() {
+            game.advance_turn();
+        }
+        game.scrap_ship(other).unwrap();
+        assert_eq!(game.fleets().groups().count(), 0);
+    }
+
+    #[test]
+    fn test_colonize_by_unloading_colonists() {
+        let mut game = GameState::new(1000.0, 1000.0);
+        let home = game
+            .galaxy
+            .add_planet(Position::new(100.0, 100.0), 100, Some(0));
+        let target = game
+            .galaxy
+            .add_planet(Position::new(105.0, 100.0), 50, None);
+        let race = game.add_race("Humans".to_string(), home.0);
+        let planet = game.galaxy.get_planet_mut(home).unwrap();
+        planet.add_materials(100.0);
+        planet.add_colonists(2.0);
+        let ship = game
+            .build_ship(home, ShipDesign::new(5.0, 0, 0.0, 0.0, 5.0))
+            .unwrap();
+        game.load_ship(ship, CargoType::Colonists, None).unwrap();
+        game.order_ship_travel(ship, target).unwrap();
+        game.advance_turn();
+
+        assert_eq!(
+            game.unload_ship(ship, CargoType::Colonists, Some(1.5)),
+            Ok(1.5)
+        );
+
+        let planet = game.galaxy.get_planet(target).unwrap();
+        assert_eq!(planet.owner(), Some(race.0));
+        assert_eq!(planet.population(), 12.0);
+        assert!(game.pending_events.contains(&TurnEvent::PlanetColonized {
+            planet: target,
+            race,
+        }));
+        // The rest of the colonists can join the new colony
+        assert_eq!(game.unload_ship(ship, CargoType::Colonists, None), Ok(0.5));
+        assert_eq!(game.galaxy.get_planet(target).unwrap().colonists(), 0.5);
+    }
+
+    #[test]
+    fn test_load_and_unload_stockpiles() {
+        let mut game = GameState::new(1000.0, 1000.0);
+        let home = game
+            .galaxy
+            .add_planet(Position::new(100.0, 100.0), 100, Some(0));
+        let other_home = game
+            .galaxy
+            .add_planet(Position::new(105.0, 100.0), 100, Some(1));
+        let empty = game
+            .galaxy
+            .add_planet(Position::new(100.0, 105.0), 50, None);
+        let race = game.add_race("Humans".to_string(), home.0);
+        game.add_race("Zorgs".to_string(), other_home.0);
+        game.galaxy
+            .get_planet_mut(home)
+            .unwrap()
+            .add_materials(100.0);
+        // Cargo 5 carries 5 + 25/10 = 7.5
+        let ship = game
+            .build_ship(home, ShipDesign::new(5.0, 0, 0.0, 0.0, 5.0))
+            .unwrap();
+        let materials = game.galaxy.get_planet(home).unwrap().materials();
+        assert_eq!(
+            game.load_ship(ship, CargoType::Capital, None),
+            Err(GameError::InsufficientStockpile {
+                planet: home,
+                resource: CargoType::Capital,
+                needed: 7.5,
+                available: 0.0,
+            })
+        );
+
+        game.galaxy.get_planet_mut(home).unwrap().add_colonists(3.0);
+        assert_eq!(
+            game.load_ship(ship, CargoType::Colonists, Some(3.0)),
+            Ok(3.0)
+        );
+        assert_eq!(game.load_ship(ship, CargoType::Materials, None), Ok(4.5));
+        assert_eq!(
+            game.load_ship(ship, CargoType::Materials, None),
+            Err(GameError::CargoHoldFull(ship))
+        );
+        assert_eq!(
+            game.galaxy.get_planet(home).unwrap().materials(),
+            materials - 4.5
+        );
+        assert_eq!(
+            game.unload_ship(ship, CargoType::Materials, Some(-1.0)),
+            Err(GameError::InvalidAmount(-1.0))
+        );
+
+        // Materials are a gift to a foreign owner; colonists are refused
+        game.ships
+            .get_mut(&ship)
+            .unwrap()
+            .set_location(ShipLocation::AtPlanet(other_home));
+        let materials = game.galaxy.get_planet(other_home).unwrap().materials();
+        assert_eq!(
+            game.unload_ship(ship, CargoType::Materials, Some(2.0)),
+            Ok(2.0)
+        );
+        assert_eq!(
+            game.galaxy.get_planet(other_home).unwrap().materials(),
+            materials + 2.0
+        );
+        assert_eq!(
+            game.unload_ship(ship, CargoType::Colonists, None),
+            Err(GameError::NotPlanetOwner {
+                planet: other_home,
+                race,
+            })
+        );
+        assert_eq!(
+            game.load_ship(ship, CargoType::Materials, None),
+            Err(GameError::NotPlanetOwner {
+                planet: other_home,
+                race,
+            })
+        );
+
+        // Materials left on an unowned planet stay there
+        game.ships
+            .get_mut(&ship)
+            .unwrap()
+            .set_location(ShipLocation::AtPlanet(empty));
+        assert_eq!(game.unload_ship(ship, CargoType::Materials, None), Ok(2.5));
+        let empty = game.galaxy.get_planet(empty).unwrap();
+        assert_eq!(empty.materials(), 2.5);
+        assert_eq!(empty.colonists(), 0.0);
+        assert_eq!(empty.owner(), None);
+    }
+
+    #[test]
+    fn test_relationship_change_event() {
+        let mut game = GameState::new(1000.0, 1000.0);
+
+        game.set_relationship(RaceId(0), RaceId(1), Relationship::Hostile);
+        // Setting the same relationship again is not a change
+        game.set_relationship(RaceId(1), RaceId(0), Relationship::Hostile);
+
+        assert_eq!(
+            game.advance_turn(),
+            &[TurnEvent::RelationshipChanged {
+                race1: RaceId(0),
+                race2: RaceId(1),
+                relationship: Relationship::Hostile,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_build_ship_errors() {
+        let mut game = GameState::new(1000.0, 1000.0);
+        let home = game
+            .galaxy
+            .add_planet(Position::new(100.0, 100.0), 100, Some(0));
+        let empty = game
+            .galaxy
+            .add_planet(Position::new(200.0, 100.0), 50, None);
+        game.add_race("Humans".to_string(), home.0);
+        let design = ShipDesign::new(1.0, 0, 0.0, 0.0, 0.0);
+
+        assert_eq!(
+            game.build_ship(PlanetId(9), design),
+            Err(GameError::UnknownPlanet(PlanetId(9)))
+        );
+        assert_eq!(
+            game.build_ship(empty, design),
+            Err(GameError::PlanetUnowned(empty))
+        );
+        assert_eq!(
+            game.build_ship(home, design),
+            Err(GameError::InsufficientStockpile {
+                planet: home,
+                resource: CargoType::Materials,
+                needed: 1.0,
+                available: 0.0,
+            })
+        );
+        assert_eq!(
+            game.build_ship(home, ShipDesign::new(1.0, 2, 0.0, 0.0, 0.0)),
+            Err(GameError::InvalidDesign(DesignError::AttacksWithoutWeapons))
+        );
+    }
+
+    #[test]
+    fn test_travel_order_errors() {
+        let mut game = GameState::new(1000.0, 1000.0);
+        let home = game
+            .galaxy
+            .add_planet(Position::new(100.0, 100.0), 100, Some(0));
+        let target = game
+            .galaxy
+            .add_planet(Position::new(200.0, 100.0), 50, None);
+        game.add_race("Humans".to_string(), home.0);
+        game.galaxy
+            .get_planet_mut(home)
+            .unwrap()
+            .add_materials(10.0);
+        let ship = game
+            .build_ship(home, ShipDesign::new(1.0, 0, 0.0, 0.0, 0.0))
+            .unwrap();
+
+        assert_eq!(
+            game.order_ship_travel(ShipId(99), target),
+            Err(GameError::UnknownShip(ShipId(99)))
+        );
+        assert_eq!(
+            game.order_ship_travel(ship, home),
+            Err(GameError::AlreadyAtDestination { ship, planet: home })
+        );
+        assert_eq!(
+            game.order_ship_travel(ship, PlanetId(42)),
+            Err(GameError::UnknownPlanet(PlanetId(42)))
+        );
+        game.order_ship_travel(ship, target).unwrap();
+        assert_eq!(
+            game.order_ship_travel(ship, target),
+            Err(GameError::ShipInFlight(ship))
+        );
+        assert_eq!(
+            game.load_ship(ship, CargoType::Materials, None),
+            Err(GameError::ShipInFlight(ship))
+        );
+
+        // Turning back before leaving cancels the trip
+        game.order_ship_travel(ship, home).unwrap();
+        assert_eq!(
+            game.get_ship(ship).unwrap().location(),
+            &ShipLocation::AtPlanet(home)
+        );
+    }
+
     #[test]
     fn test_friendly_ships_no_bombing() {
         let mut game = GameState::new(1000.0, 1000.0);
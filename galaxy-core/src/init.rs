@@ -1,16 +1,24 @@
 use rand::Rng;
+use serde::Deserialize;
+use serde::Serialize;
 
 use crate::game_state::GameState;
 use crate::planet::Position;
 use crate::racebot::Personality;
 
 /// Configuration for initializing a new game
-#[derive(Debug, Clone)]
+///
+/// Missing fields take their default values when deserialized.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GameConfig {
     pub galaxy_width: f64,
     pub galaxy_height: f64,
     pub num_races: u32,
     pub num_planets: u32,
+    /// Seed for the game's random stream; the same seed and the same orders
+    /// always replay the same game
+    pub seed: u64,
 }
 
 impl Default for GameConfig {
@@ -20,14 +28,14 @@ impl Default for GameConfig {
             galaxy_height: 1000.0,
             num_races: 4,
             num_planets: 20,
+            seed: rand::random(),
         }
     }
 }
 
 /// Initialize a new game with random galaxy generation
 pub fn initialize_game(config: GameConfig) -> GameState {
-    let mut rng = rand::thread_rng();
-    let mut game = GameState::new(config.galaxy_width, config.galaxy_height);
+    let mut game = GameState::with_seed(config.galaxy_width, config.galaxy_height, config.seed);
 
     // Validate configuration
     if config.num_planets < config.num_races {
@@ -35,6 +43,7 @@ pub fn initialize_game(config: GameConfig) -> GameState {
     }
 
     // Generate random positions for all planets
+    let rng = game.rng_mut();
     let mut planet_positions = Vec::new();
     for _ in 0..config.num_planets {
         // Ensure planets are well-distributed
@@ -44,8 +53,8 @@ pub fn initialize_game(config: GameConfig) -> GameState {
     }
 
     // Create home planets for each race (first num_races planets)
-    let race_names = generate_race_names(config.num_races);
-    let personalities = generate_personalities(config.num_races, &mut rng);
+    let race_names = generate_race_names(config.num_races, rng);
+    let personalities = generate_personalities(config.num_races, rng);
 
     for i in 0..config.num_races {
         let position = planet_positions[i as usize];
@@ -60,11 +69,11 @@ pub fn initialize_game(config: GameConfig) -> GameState {
     // Create remaining planets (random size 10-300, random resources 0.01-10.00)
     for i in config.num_races..config.num_planets {
         let position = planet_positions[i as usize];
-        let size = rng.gen_range(10..=300);
+        let size = game.rng_mut().gen_range(10..=300);
         let planet_id = game.galaxy_mut().add_planet(position, size, None);
 
         // Set random resources (average 1.0)
-        let resources = rng.gen_range(0.01..=10.0);
+        let resources = game.rng_mut().gen_range(0.01..=10.0);
         if let Some(planet) = game.galaxy_mut().get_planet_mut(planet_id) {
             planet.set_resources(resources);
         }
@@ -74,7 +83,7 @@ pub fn initialize_game(config: GameConfig) -> GameState {
 }
 
 /// Generate random race names
-fn generate_race_names(count: u32) -> Vec<String> {
+fn generate_race_names(count: u32, rng: &mut impl Rng) -> Vec<String> {
     let prefixes = [
         "Zor", "Kar", "Thal", "Vex", "Nyx", "Drak", "Qua", "Xen", "Mor", "Lux", "Kor", "Zal",
         "Pyr", "Vok", "Rax", "Syl",
@@ -85,7 +94,6 @@ fn generate_race_names(count: u32) -> Vec<String> {
         "um", "is",
     ];
 
-    let mut rng = rand::thread_rng();
     let mut names = Vec::new();
     let mut used_names = std::collections::HashSet::new();
 
@@ -126,7 +134,10 @@ fn generate_personalities(count: u32, rng: &mut impl Rng) -> Vec<Personality> {
 
 #[cfg(test)]
 mod tests {
+    use rand::SeedableRng;
+
     use super::*;
+    use crate::game_state::GameRng;
 
     #[test]
     fn test_default_config() {
@@ -144,6 +155,7 @@ mod tests {
             galaxy_height: 500.0,
             num_races: 3,
             num_planets: 10,
+            seed: 1,
         };
 
         let game = initialize_game(config);
@@ -168,6 +180,7 @@ mod tests {
             galaxy_height: 1000.0,
             num_races: 2,
             num_planets: 5,
+            seed: 1,
         };
 
         let game = initialize_game(config);
@@ -192,6 +205,7 @@ mod tests {
             galaxy_height: 1000.0,
             num_races: 2,
             num_planets: 10,
+            seed: 1,
         };
 
         let game = initialize_game(config);
@@ -207,9 +221,62 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_same_seed_same_galaxy() {
+        let config = GameConfig {
+            seed: 1234,
+            ..GameConfig::default()
+        };
+
+        let game1 = initialize_game(config.clone());
+        let game2 = initialize_game(config);
+
+        let snapshot = |game: &GameState| {
+            let mut planets: Vec<_> = game
+                .galaxy()
+                .planets()
+                .map(|p| (p.id().0, *p.position(), p.size(), p.resources()))
+                .collect();
+            planets.sort_by_key(|p| p.0);
+            let mut races: Vec<_> = game
+                .races()
+                .map(|r| (r.id().0, r.name().to_string()))
+                .collect();
+            races.sort();
+            (planets, races)
+        };
+
+        assert_eq!(snapshot(&game1), snapshot(&game2));
+    }
+
+    #[test]
+    fn test_different_seed_different_galaxy() {
+        let game1 = initialize_game(GameConfig {
+            seed: 1,
+            ..GameConfig::default()
+        });
+        let game2 = initialize_game(GameConfig {
+            seed: 2,
+            ..GameConfig::default()
+        });
+
+        let positions = |game: &GameState| {
+            let mut positions: Vec<_> = game
+                .galaxy()
+                .planets()
+                .map(|p| (p.id().0, *p.position()))
+                .collect();
+            positions.sort_by_key(|p| p.0);
+            positions
+        };
+
+        assert_ne!(positions(&game1), positions(&game2));
+    }
+
     #[test]
     fn test_race_names_unique() {
-        let names = generate_race_names(10);
+        let mut rng = GameRng::seed_from_u64(0);
+        let names = generate_race_names(10, &mut rng);
         let unique_names: std::collections::HashSet<_> = names.iter().collect();
 
         assert_eq!(names.len(), 10);
@@ -224,6 +291,7 @@ mod tests {
             galaxy_height: 1000.0,
             num_races: 10,
             num_planets: 5, // Less than races!
+            seed: 1,
         };
 
         initialize_game(config);
@@ -1,10 +1,18 @@
 pub mod combat;
 pub mod diplomacy;
+pub mod error;
+pub mod event;
+pub mod fleet;
 pub mod galaxy;
 pub mod game_state;
 pub mod init;
+pub mod orders;
 pub mod planet;
 pub mod race;
 pub mod racebot;
+#[cfg(feature = "bevy")]
 pub mod rendering;
+pub mod report;
+pub mod route;
+pub mod save;
 pub mod ship;
@@ -0,0 +1,1053 @@
+use std::fmt;
+
+use crate::diplomacy::Relationship;
+use crate::error::GameError;
+use crate::event::TurnEvent;
+use crate::fleet::GroupId;
+use crate::game_state::GameState;
+use crate::planet::PlanetId;
+use crate::planet::ProductionType;
+use crate::race::RaceId;
+use crate::race::TechnologyType;
+use crate::ship::CargoType;
+use crate::ship::ShipDesign;
+use crate::ship::ShipId;
+
+/// A single order in the GalaxyNG-style orders language
+///
+/// ```text
+/// d <name> <drive> <attacks> <weapons> <shields> <cargo>   design a ship type
+/// e <name>                                                delete an unused ship type
+/// p <planet> <MAT|CAP|DRIVE|WEAPONS|SHIELDS|CARGO|type>    set production
+/// s <ship> <planet>                                       send a ship (or turn it back)
+/// g <ship>                                                upgrade a ship to current tech
+/// x <ship>                                                scrap a ship at an own planet
+/// l <ship> <COL|MAT|CAP> [amount]                         load cargo
+/// u <ship> [COL|MAT|CAP] [amount]                         unload cargo
+/// r <planet> <COL|MAT|CAP|EMP> [planet]                   set or cancel a route
+/// m <ship> [ship...]                                      merge identical ships into a group
+/// b <group> <count>                                       break ships off a group
+/// j <group> [fleet]                                       join (or leave) a fleet
+/// f <fleet> <planet>                                      send a fleet (or turn it back)
+/// i <group> <planet>                                      intercept ships leaving a planet
+/// a <race>                                                declare alliance
+/// w <race>                                                declare war
+/// p <race>                                                make peace
+/// ```
+///
+/// Everything after a `;` is a comment. Races are given by name or number.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Order {
+    Design {
+        name: String,
+        design: ShipDesign,
+    },
+    /// Delete a ship type no ship or planet uses
+    DeleteDesign {
+        name: String,
+    },
+    Production {
+        planet: PlanetId,
+        production: ProductionOrder,
+    },
+    Send {
+        ship: ShipId,
+        destination: PlanetId,
+    },
+    /// Upgrade a ship at one of the race's planets
+    Upgrade {
+        ship: ShipId,
+    },
+    /// Scrap a ship at one of the race's planets
+    Scrap {
+        ship: ShipId,
+    },
+    /// Load cargo; without an amount the hold is filled
+    Load {
+        ship: ShipId,
+        cargo: CargoType,
+        amount: Option<f64>,
+    },
+    /// Unload cargo; without a type everything that may be dropped there is
+    Unload {
+        ship: ShipId,
+        cargo: Option<CargoType>,
+        amount: Option<f64>,
+    },
+    /// Set a route (`cargo: None` sends empty ships); without a
+    /// destination the route is cancelled
+    Route {
+        origin: PlanetId,
+        cargo: Option<CargoType>,
+        destination: Option<PlanetId>,
+    },
+    /// Merge ships into the group of the first one
+    Merge {
+        ships: Vec<ShipId>,
+    },
+    /// Break ships off a group into a new one
+    Split {
+        group: GroupId,
+        count: usize,
+    },
+    /// Add a group to a fleet; without a fleet the group leaves its fleet
+    JoinFleet {
+        group: GroupId,
+        fleet: Option<String>,
+    },
+    SendFleet {
+        fleet: String,
+        destination: PlanetId,
+    },
+    /// Send a group after foreign ships leaving a planet
+    Intercept {
+        group: GroupId,
+        planet: PlanetId,
+    },
+    Diplomacy {
+        race: String,
+        relationship: Relationship,
+    },
+}
+
+/// What a `p` order asks a planet to produce
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProductionOrder {
+    Materials,
+    Capital,
+    Research(TechnologyType),
+    /// Ships of the named type, resolved against the race's designs
+    Ships(String),
+}
+
+/// An order together with the line it came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderLine {
+    pub line: usize,
+    pub order: Order,
+}
+
+/// Why an order could not be parsed or carried out
+#[derive(Debug, Clone, PartialEq)]
+pub enum OrderError {
+    UnknownCommand(String),
+    /// Wrong number of arguments; holds the expected form
+    Usage(&'static str),
+    InvalidNumber {
+        what: &'static str,
+        word: String,
+    },
+    InvalidMass(String),
+    InvalidAmount(String),
+    UnknownCargo(String),
+    /// No race with this name or number
+    UnknownRace(String),
+    /// The order parsed but the game refused it
+    Rejected(GameError),
+}
+
+impl fmt::Display for OrderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownCommand(command) => write!(f, "unknown order `{command}`"),
+            Self::Usage(usage) => write!(f, "usage: {usage}"),
+            Self::InvalidNumber { what, word } => write!(f, "invalid {what} `{word}`"),
+            Self::InvalidMass(word) => write!(f, "invalid mass `{word}`"),
+            Self::InvalidAmount(word) => write!(f, "invalid amount `{word}`"),
+            Self::UnknownCargo(word) => write!(f, "unknown cargo type `{word}`"),
+            Self::UnknownRace(race) => write!(f, "unknown race `{race}`"),
+            Self::Rejected(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for OrderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Rejected(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<GameError> for OrderError {
+    fn from(err: GameError) -> Self {
+        Self::Rejected(err)
+    }
+}
+
+/// An [`OrderError`] together with the line it came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderLineError {
+    /// 1-based line number in the orders text, 0 for the file as a whole
+    pub line: usize,
+    pub error: OrderError,
+}
+
+impl fmt::Display for OrderLineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+impl std::error::Error for OrderLineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Parse an orders file
+///
+/// Returns every order that parsed along with an error for each line that did
+/// not.
+pub fn parse_orders(text: &str) -> (Vec<OrderLine>, Vec<OrderLineError>) {
+    let mut orders = Vec::new();
+    let mut errors = Vec::new();
+
+    for (index, raw) in text.lines().enumerate() {
+        let line = index + 1;
+        let content = raw.split(';').next().unwrap_or_default();
+        let words: Vec<&str> = content.split_whitespace().collect();
+        let Some((command, args)) = words.split_first() else {
+            continue;
+        };
+        // `#GALAXY`/`#END` style header lines carry no orders
+        if command.starts_with('#') {
+            continue;
+        }
+
+        match parse_order(command, args) {
+            Ok(order) => orders.push(OrderLine { line, order }),
+            Err(error) => errors.push(OrderLineError { line, error }),
+        }
+    }
+
+    (orders, errors)
+}
+
+fn parse_order(command: &str, args: &[&str]) -> Result<Order, OrderError> {
+    match command.to_ascii_lowercase().as_str() {
+        "d" => {
+            let [name, drive, attacks, weapons, shields, cargo] = args else {
+                return Err(OrderError::Usage(
+                    "d <name> <drive> <attacks> <weapons> <shields> <cargo>",
+                ));
+            };
+            let design = ShipDesign::new(
+                parse_mass(drive)?,
+                attacks.parse().map_err(|_| OrderError::InvalidNumber {
+                    what: "number of attacks",
+                    word: attacks.to_string(),
+                })?,
+                parse_mass(weapons)?,
+                parse_mass(shields)?,
+                parse_mass(cargo)?,
+            );
+            Ok(Order::Design {
+                name: name.to_string(),
+                design,
+            })
+        }
+        "e" => {
+            let [name] = args else {
+                return Err(OrderError::Usage("e <name>"));
+            };
+            Ok(Order::DeleteDesign {
+                name: name.to_string(),
+            })
+        }
+        "p" => match args {
+            [race] => Ok(Order::Diplomacy {
+                race: race.to_string(),
+                relationship: Relationship::Neutral,
+            }),
+            [planet, production] => Ok(Order::Production {
+                planet: PlanetId(parse_id(planet, "planet number")?),
+                production: parse_production(production),
+            }),
+            _ => Err(OrderError::Usage("p <planet> <production> or p <race>")),
+        },
+        "s" => {
+            let [ship, planet] = args else {
+                return Err(OrderError::Usage("s <ship> <planet>"));
+            };
+            Ok(Order::Send {
+                ship: ShipId(parse_id(ship, "ship number")?),
+                destination: PlanetId(parse_id(planet, "planet number")?),
+            })
+        }
+        "g" => {
+            let [ship] = args else {
+                return Err(OrderError::Usage("g <ship>"));
+            };
+            Ok(Order::Upgrade {
+                ship: ShipId(parse_id(ship, "ship number")?),
+            })
+        }
+        "x" => {
+            let [ship] = args else {
+                return Err(OrderError::Usage("x <ship>"));
+            };
+            Ok(Order::Scrap {
+                ship: ShipId(parse_id(ship, "ship number")?),
+            })
+        }
+        "l" => {
+            let (ship, cargo, amount) = match args {
+                [ship, cargo] => (ship, cargo, None),
+                [ship, cargo, amount] => (ship, cargo, Some(parse_amount(amount)?)),
+                _ => return Err(OrderError::Usage("l <ship> <COL|MAT|CAP> [amount]")),
+            };
+            Ok(Order::Load {
+                ship: ShipId(parse_id(ship, "ship number")?),
+                cargo: parse_cargo(cargo)?,
+                amount,
+            })
+        }
+        "u" => {
+            let (ship, cargo, amount) = match args {
+                [ship] => (ship, None, None),
+                [ship, cargo] => (ship, Some(parse_cargo(cargo)?), None),
+                [ship, cargo, amount] => {
+                    (ship, Some(parse_cargo(cargo)?), Some(parse_amount(amount)?))
+                }
+                _ => return Err(OrderError::Usage("u <ship> [COL|MAT|CAP] [amount]")),
+            };
+            Ok(Order::Unload {
+                ship: ShipId(parse_id(ship, "ship number")?),
+                cargo,
+                amount,
+            })
+        }
+        "r" => {
+            let (origin, cargo, destination) = match args {
+                [origin, cargo] => (origin, cargo, None),
+                [origin, cargo, destination] => (
+                    origin,
+                    cargo,
+                    Some(PlanetId(parse_id(destination, "planet number")?)),
+                ),
+                _ => return Err(OrderError::Usage("r <planet> <COL|MAT|CAP|EMP> [planet]")),
+            };
+            let cargo = match cargo.to_ascii_uppercase().as_str() {
+                "EMP" | "EMPTY" => None,
+                _ => Some(parse_cargo(cargo)?),
+            };
+            Ok(Order::Route {
+                origin: PlanetId(parse_id(origin, "planet number")?),
+                cargo,
+                destination,
+            })
+        }
+        "m" => {
+            if args.is_empty() {
+                return Err(OrderError::Usage("m <ship> [ship...]"));
+            }
+            let ships = args
+                .iter()
+                .map(|ship| parse_id(ship, "ship number").map(ShipId))
+                .collect::<Result<_, _>>()?;
+            Ok(Order::Merge { ships })
+        }
+        "b" => {
+            let [group, count] = args else {
+                return Err(OrderError::Usage("b <group> <count>"));
+            };
+            Ok(Order::Split {
+                group: GroupId(parse_id(group, "group number")?),
+                count: parse_id(count, "number of ships")? as usize,
+            })
+        }
+        "j" => {
+            let (group, fleet) = match args {
+                [group] => (group, None),
+                [group, fleet] => (group, Some(fleet.to_string())),
+                _ => return Err(OrderError::Usage("j <group> [fleet]")),
+            };
+            Ok(Order::JoinFleet {
+                group: GroupId(parse_id(group, "group number")?),
+                fleet,
+            })
+        }
+        "f" => {
+            let [fleet, planet] = args else {
+                return Err(OrderError::Usage("f <fleet> <planet>"));
+            };
+            Ok(Order::SendFleet {
+                fleet: fleet.to_string(),
+                destination: PlanetId(parse_id(planet, "planet number")?),
+            })
+        }
+        "i" => {
+            let [group, planet] = args else {
+                return Err(OrderError::Usage("i <group> <planet>"));
+            };
+            Ok(Order::Intercept {
+                group: GroupId(parse_id(group, "group number")?),
+                planet: PlanetId(parse_id(planet, "planet number")?),
+            })
+        }
+        "a" => {
+            let [race] = args else {
+                return Err(OrderError::Usage("a <race>"));
+            };
+            Ok(Order::Diplomacy {
+                race: race.to_string(),
+                relationship: Relationship::Friendly,
+            })
+        }
+        "w" => {
+            let [race] = args else {
+                return Err(OrderError::Usage("w <race>"));
+            };
+            Ok(Order::Diplomacy {
+                race: race.to_string(),
+                relationship: Relationship::Hostile,
+            })
+        }
+        _ => Err(OrderError::UnknownCommand(command.to_string())),
+    }
+}
+
+fn parse_id(word: &str, what: &'static str) -> Result<u32, OrderError> {
+    word.trim_start_matches('#')
+        .parse()
+        .map_err(|_| OrderError::InvalidNumber {
+            what,
+            word: word.to_string(),
+        })
+}
+
+fn parse_mass(word: &str) -> Result<f64, OrderError> {
+    match word.parse::<f64>() {
+        Ok(mass) if mass.is_finite() && mass >= 0.0 => Ok(mass),
+        _ => Err(OrderError::InvalidMass(word.to_string())),
+    }
+}
+
+fn parse_amount(word: &str) -> Result<f64, OrderError> {
+    match word.parse::<f64>() {
+        Ok(amount) if amount.is_finite() && amount > 0.0 => Ok(amount),
+        _ => Err(OrderError::InvalidAmount(word.to_string())),
+    }
+}
+
+fn parse_cargo(word: &str) -> Result<CargoType, OrderError> {
+    match word.to_ascii_uppercase().as_str() {
+        "COL" | "COLONISTS" => Ok(CargoType::Colonists),
+        "MAT" | "MATERIALS" => Ok(CargoType::Materials),
+        "CAP" | "CAPITAL" => Ok(CargoType::Capital),
+        _ => Err(OrderError::UnknownCargo(word.to_string())),
+    }
+}
+
+fn parse_production(word: &str) -> ProductionOrder {
+    match word.to_ascii_uppercase().as_str() {
+        "MAT" | "MATERIALS" => ProductionOrder::Materials,
+        "CAP" | "CAPITAL" => ProductionOrder::Capital,
+        "DRIVE" => ProductionOrder::Research(TechnologyType::Drive),
+        "WEAPONS" => ProductionOrder::Research(TechnologyType::Weapon),
+        "SHIELDS" => ProductionOrder::Research(TechnologyType::Shield),
+        "CARGO" => ProductionOrder::Research(TechnologyType::Cargo),
+        _ => ProductionOrder::Ships(word.to_string()),
+    }
+}
+
+impl GameState {
+    /// Carry out a race's orders file
+    ///
+    /// Orders are applied in file order. Lines that fail to parse or cannot be
+    /// carried out are skipped and reported; the remaining orders still take
+    /// effect. Orders the game refused are also recorded as
+    /// [`TurnEvent::OrderRejected`] so they show up in the race's report.
+    pub fn apply_orders(
+        &mut self,
+        race_id: RaceId,
+        orders: &str,
+    ) -> Result<(), Vec<OrderLineError>> {
+        if self.get_race(race_id).is_none() {
+            return Err(vec![OrderLineError {
+                line: 0,
+                error: GameError::UnknownRace(race_id).into(),
+            }]);
+        }
+
+        let (orders, mut errors) = parse_orders(orders);
+        for OrderLine { line, order } in orders {
+            if let Err(error) = self.apply_order(race_id, order) {
+                if let OrderError::Rejected(err) = &error {
+                    self.record(TurnEvent::OrderRejected {
+                        race: race_id,
+                        error: err.clone(),
+                    });
+                }
+                errors.push(OrderLineError { line, error });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            errors.sort_by_key(|err| err.line);
+            Err(errors)
+        }
+    }
+
+    fn apply_order(&mut self, race_id: RaceId, order: Order) -> Result<(), OrderError> {
+        match order {
+            Order::Design { name, design } => {
+                let race = self.get_race_mut(race_id).expect("race checked");
+                race.add_ship_type(name, design)?;
+            }
+            Order::DeleteDesign { name } => self.delete_ship_type(race_id, &name)?,
+            Order::Production { planet, production } => {
+                let production = match production {
+                    ProductionOrder::Materials => ProductionType::Materials,
+                    ProductionOrder::Capital => ProductionType::Capital,
+                    ProductionOrder::Research(tech) => ProductionType::Research(tech),
+                    ProductionOrder::Ships(name) => {
+                        let race = self.get_race(race_id).expect("race checked");
+                        let ship_type = race
+                            .ship_type_by_name(&name)
+                            .ok_or(GameError::UnknownShipType(name))?;
+                        ProductionType::Ships(ship_type)
+                    }
+                };
+                let planet_id = planet;
+                let planet = self
+                    .galaxy_mut()
+                    .get_planet_mut(planet_id)
+                    .ok_or(GameError::UnknownPlanet(planet_id))?;
+                if planet.owner() != Some(race_id.0) {
+                    return Err(GameError::NotPlanetOwner {
+                        planet: planet_id,
+                        race: race_id,
+                    }
+                    .into());
+                }
+                planet.set_production_type(production);
+            }
+            Order::Send { ship, destination } => {
+                self.check_ship_owner(race_id, ship)?;
+                self.order_ship_travel(ship, destination)?;
+            }
+            Order::Upgrade { ship } => {
+                self.check_ship_owner(race_id, ship)?;
+                self.upgrade_ship(ship)?;
+            }
+            Order::Scrap { ship } => {
+                self.check_ship_owner(race_id, ship)?;
+                self.scrap_ship(ship)?;
+            }
+            Order::Load {
+                ship,
+                cargo,
+                amount,
+            } => {
+                self.check_ship_owner(race_id, ship)?;
+                self.load_ship(ship, cargo, amount)?;
+            }
+            Order::Unload {
+                ship,
+                cargo,
+                amount,
+            } => {
+                self.check_ship_owner(race_id, ship)?;
+                let cargo_types = match cargo {
+                    Some(cargo) => vec![cargo],
+                    // Only what is on board and may be dropped here, so that
+                    // colonists stay aboard over a foreign planet while the
+                    // materials and capital are still delivered
+                    None => {
+                        let hold = self.get_ship(ship).ok_or(GameError::UnknownShip(ship))?;
+                        let foreign = hold
+                            .location()
+                            .planet_id()
+                            .and_then(|planet| self.galaxy().get_planet(planet))
+                            .and_then(|planet| planet.owner())
+                            .is_some_and(|owner| owner != race_id.0);
+                        [
+                            CargoType::Colonists,
+                            CargoType::Materials,
+                            CargoType::Capital,
+                        ]
+                        .into_iter()
+                        .filter(|cargo| hold.cargo(*cargo) > 0.0)
+                        .filter(|cargo| !(foreign && *cargo == CargoType::Colonists))
+                        .collect()
+                    }
+                };
+                for cargo in cargo_types {
+                    self.unload_ship(ship, cargo, amount)?;
+                }
+            }
+            Order::Route {
+                origin,
+                cargo,
+                destination,
+            } => self.set_route(race_id, origin, cargo, destination)?,
+            Order::Merge { ships } => {
+                self.merge_ships(race_id, &ships)?;
+            }
+            Order::Split { group, count } => {
+                self.split_group(race_id, group, count)?;
+            }
+            Order::JoinFleet { group, fleet } => match fleet {
+                Some(fleet) => {
+                    self.join_fleet(race_id, group, &fleet)?;
+                }
+                None => self.leave_fleet(race_id, group)?,
+            },
+            Order::SendFleet { fleet, destination } => {
+                self.send_fleet(race_id, &fleet, destination)?;
+            }
+            Order::Intercept { group, planet } => {
+                self.intercept(race_id, group, planet)?;
+            }
+            Order::Diplomacy { race, relationship } => {
+                let other = self
+                    .races()
+                    .find(|r| r.name().eq_ignore_ascii_case(&race) || r.id().0.to_string() == race)
+                    .map(|r| r.id())
+                    .ok_or(OrderError::UnknownRace(race))?;
+                if other == race_id {
+                    return Err(GameError::SelfRelationship(race_id).into());
+                }
+                self.set_relationship(race_id, other, relationship);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_ship_owner(&self, race_id: RaceId, ship_id: ShipId) -> Result<(), GameError> {
+        let ship = self
+            .get_ship(ship_id)
+            .ok_or(GameError::UnknownShip(ship_id))?;
+        if ship.owner() == race_id {
+            Ok(())
+        } else {
+            Err(GameError::NotShipOwner {
+                ship: ship_id,
+                race: race_id,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::planet::Position;
+    use crate::race::RESEARCH_PER_LEVEL;
+    use crate::ship::ShipLocation;
+
+    fn game_with_race() -> (GameState, RaceId) {
+        let mut game = GameState::new(1000.0, 1000.0);
+        let home = game
+            .galaxy_mut()
+            .add_planet(Position::new(100.0, 100.0), 100, Some(0));
+        game.galaxy_mut()
+            .add_planet(Position::new(150.0, 100.0), 50, None);
+        let other_home = game
+            .galaxy_mut()
+            .add_planet(Position::new(900.0, 900.0), 100, Some(1));
+        let race = game.add_race("Humans".to_string(), home.0);
+        game.add_race("Zorgs".to_string(), other_home.0);
+        game.galaxy_mut()
+            .get_planet_mut(home)
+            .unwrap()
+            .add_materials(100.0);
+        (game, race)
+    }
+
+    #[test]
+    fn test_parse_orders() {
+        let text = "\
+#GALAXY Humans
+d Scout 1 0 0 0 1   ; a cheap scout
+p 0 Scout
+P 3 drive
+s 4 2
+l 4 COL 2.5
+u 4
+a Zorgs
+w 1
+p Zorgs
+#END
+";
+        let (orders, errors) = parse_orders(text);
+
+        assert!(errors.is_empty(), "{errors:?}");
+        let orders: Vec<Order> = orders.into_iter().map(|o| o.order).collect();
+        assert_eq!(
+            orders,
+            vec![
+                Order::Design {
+                    name: "Scout".to_string(),
+                    design: ShipDesign::new(1.0, 0, 0.0, 0.0, 1.0),
+                },
+                Order::Production {
+                    planet: PlanetId(0),
+                    production: ProductionOrder::Ships("Scout".to_string()),
+                },
+                Order::Production {
+                    planet: PlanetId(3),
+                    production: ProductionOrder::Research(TechnologyType::Drive),
+                },
+                Order::Send {
+                    ship: ShipId(4),
+                    destination: PlanetId(2),
+                },
+                Order::Load {
+                    ship: ShipId(4),
+                    cargo: CargoType::Colonists,
+                    amount: Some(2.5),
+                },
+                Order::Unload {
+                    ship: ShipId(4),
+                    cargo: None,
+                    amount: None,
+                },
+                Order::Diplomacy {
+                    race: "Zorgs".to_string(),
+                    relationship: Relationship::Friendly,
+                },
+                Order::Diplomacy {
+                    race: "1".to_string(),
+                    relationship: Relationship::Hostile,
+                },
+                Order::Diplomacy {
+                    race: "Zorgs".to_string(),
+                    relationship: Relationship::Neutral,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors_have_line_numbers() {
+        let text = "s 1 2\n\nz 1\ns one 2\nl 1 GOLD\nd Bad 1 0 0\n";
+        let (orders, errors) = parse_orders(text);
+
+        assert_eq!(orders.len(), 1);
+        let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![3, 4, 5, 6]);
+        assert_eq!(errors[0].to_string(), "line 3: unknown order `z`");
+        assert_eq!(
+            errors[1].error,
+            OrderError::InvalidNumber {
+                what: "ship number",
+                word: "one".to_string(),
+            }
+        );
+        assert_eq!(
+            errors[2].error,
+            OrderError::UnknownCargo("GOLD".to_string())
+        );
+        assert_eq!(
+            errors[3].error,
+            OrderError::Usage("d <name> <drive> <attacks> <weapons> <shields> <cargo>")
+        );
+    }
+
+    #[test]
+    fn test_apply_orders() {
+        let (mut game, race) = game_with_race();
+        let ship = game
+            .build_ship(PlanetId(0), ShipDesign::new(1.0, 0, 0.0, 0.0, 1.0))
+            .unwrap();
+        game.galaxy_mut()
+            .get_planet_mut(PlanetId(0))
+            .unwrap()
+            .add_colonists(5.0);
+
+        let orders = format!(
+            "d Freighter 1 0 0 0 2\np 0 Freighter\nl {} COL 1\ns {} 1\nw Zorgs\n",
+            ship.0, ship.0
+        );
+        game.apply_orders(race, &orders).unwrap();
+
+        let freighter = game
+            .get_race(race)
+            .unwrap()
+            .ship_type_by_name("Freighter")
+            .unwrap();
+        let home = game.galaxy().get_planet(PlanetId(0)).unwrap();
+        assert_eq!(home.production_type(), ProductionType::Ships(freighter));
+        assert_eq!(home.colonists(), 4.0);
+
+        let ship = game.get_ship(ship).unwrap();
+        assert_eq!(ship.cargo(CargoType::Colonists), 1.0);
+        assert!(ship.location().is_traveling());
+        assert!(game.diplomacy().are_hostile(race, RaceId(1)));
+    }
+
+    #[test]
+    fn test_unload_everything_on_a_foreign_planet() {
+        let (mut game, race) = game_with_race();
+        let ship = game
+            .build_ship(PlanetId(0), ShipDesign::new(1.0, 0, 0.0, 0.0, 2.0))
+            .unwrap();
+        game.galaxy_mut()
+            .get_planet_mut(PlanetId(0))
+            .unwrap()
+            .add_colonists(5.0);
+        game.apply_orders(race, &format!("l {} COL 1\nl {} MAT 1\n", ship.0, ship.0))
+            .unwrap();
+        game.get_ship_mut(ship)
+            .unwrap()
+            .set_location(ShipLocation::AtPlanet(PlanetId(2)));
+        let materials = game.galaxy().get_planet(PlanetId(2)).unwrap().materials();
+
+        game.apply_orders(race, &format!("u {}", ship.0)).unwrap();
+
+        // The materials go to the Zorgs; the colonists stay on board
+        let planet = game.galaxy().get_planet(PlanetId(2)).unwrap();
+        assert_eq!(planet.materials(), materials + 1.0);
+        assert_eq!(planet.owner(), Some(1));
+        let ship = game.get_ship(ship).unwrap();
+        assert_eq!(ship.cargo(CargoType::Materials), 0.0);
+        assert_eq!(ship.cargo(CargoType::Colonists), 1.0);
+    }
+
+    #[test]
+    fn test_upgrade_order() {
+        let (mut game, race) = game_with_race();
+        let ship = game
+            .build_ship(PlanetId(0), ShipDesign::new(2.0, 0, 0.0, 0.0, 0.0))
+            .unwrap();
+        game.get_race_mut(race)
+            .unwrap()
+            .add_research(TechnologyType::Drive, RESEARCH_PER_LEVEL);
+
+        game.apply_orders(race, &format!("g {}", ship.0)).unwrap();
+
+        // 10 × 2 × (1 − 1/2) production
+        let home = game.galaxy().get_planet(PlanetId(0)).unwrap();
+        assert_eq!(home.available_production(), home.production() - 10.0);
+        assert_eq!(game.get_ship(ship).unwrap().tech().drive_level(), 2);
+    }
+
+    #[test]
+    fn test_scrap_order() {
+        let (mut game, race) = game_with_race();
+        let ship = game
+            .build_ship(PlanetId(0), ShipDesign::new(2.0, 0, 0.0, 0.0, 0.0))
+            .unwrap();
+        let materials = game.galaxy().get_planet(PlanetId(0)).unwrap().materials();
+
+        game.apply_orders(race, &format!("x {}", ship.0)).unwrap();
+
+        assert!(game.get_ship(ship).is_none());
+        assert_eq!(
+            game.galaxy().get_planet(PlanetId(0)).unwrap().materials(),
+            materials + 1.0
+        );
+        assert_eq!(
+            game.apply_orders(race, "x").unwrap_err()[0].error,
+            OrderError::Usage("x <ship>")
+        );
+    }
+
+    #[test]
+    fn test_route_orders() {
+        let (mut game, race) = game_with_race();
+
+        game.apply_orders(race, "r 0 MAT 1\nr 0 EMP 1\nr 0 mat\n")
+            .unwrap();
+        let errors = game
+            .apply_orders(race, "r 1 COL 0\nr 0 GOLD 1\n")
+            .unwrap_err();
+
+        assert_eq!(
+            game.get_race(race).unwrap().routes(),
+            &[crate::route::Route {
+                origin: PlanetId(0),
+                destination: PlanetId(1),
+                cargo: None,
+            }]
+        );
+        let errors: Vec<&OrderError> = errors.iter().map(|e| &e.error).collect();
+        assert_eq!(
+            errors,
+            vec![
+                &OrderError::Rejected(GameError::NotPlanetOwner {
+                    planet: PlanetId(1),
+                    race,
+                }),
+                &OrderError::UnknownCargo("GOLD".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_group_and_fleet_orders() {
+        let (mut game, race) = game_with_race();
+        let ships: Vec<ShipId> = (0..3)
+            .map(|_| {
+                game.build_ship(PlanetId(0), ShipDesign::new(1.0, 0, 0.0, 0.0, 0.0))
+                    .unwrap()
+            })
+            .collect();
+
+        game.apply_orders(
+            race,
+            "m 0 1 2
+b 0 1
+j 0 Armada
+j 1 Armada
+j 1
+f Armada 1
+",
+        )
+        .unwrap();
+        let errors = game
+            .apply_orders(
+                race,
+                "f Navy 1
+b 1 1
+m x
+i 7 2
+",
+            )
+            .unwrap_err();
+
+        let traveling: Vec<bool> = ships
+            .iter()
+            .map(|id| game.get_ship(*id).unwrap().location().is_traveling())
+            .collect();
+        assert_eq!(traveling, vec![true, true, false]);
+        let errors: Vec<&OrderError> = errors.iter().map(|e| &e.error).collect();
+        assert_eq!(
+            errors,
+            vec![
+                &OrderError::Rejected(GameError::UnknownFleet("Navy".to_string())),
+                &OrderError::Rejected(GameError::InvalidSplit {
+                    group: GroupId(1),
+                    count: 1,
+                }),
+                &OrderError::InvalidNumber {
+                    what: "ship number",
+                    word: "x".to_string(),
+                },
+                &OrderError::Rejected(GameError::UnknownGroup(GroupId(7))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_delete_design_order() {
+        let (mut game, race) = game_with_race();
+        game.apply_orders(race, "d Scout 1 0 0 0 0\nd Drone 1 0 0 1 0\np 0 Scout\n")
+            .unwrap();
+
+        let errors = game.apply_orders(race, "e Scout\ne Drone\n").unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, 1);
+        assert_eq!(
+            errors[0].error,
+            OrderError::Rejected(GameError::ShipTypeInUse("Scout".to_string()))
+        );
+        let humans = game.get_race(race).unwrap();
+        assert!(humans.ship_type_by_name("Scout").is_some());
+        assert!(humans.ship_type_by_name("Drone").is_none());
+    }
+
+    #[test]
+    fn test_research_production_order() {
+        let (mut game, race) = game_with_race();
+
+        game.apply_orders(race, "p 0 SHIELDS").unwrap();
+
+        let home = game.galaxy().get_planet(PlanetId(0)).unwrap();
+        assert_eq!(
+            home.production_type(),
+            ProductionType::Research(TechnologyType::Shield)
+        );
+    }
+
+    #[test]
+    fn test_invalid_orders_are_reported_and_skipped() {
+        let (mut game, race) = game_with_race();
+
+        let errors = game
+            .apply_orders(
+                race,
+                "p 2 MAT\np 0 CAP\ns 9 1\np 0 Battleship\nbogus\na Humans",
+            )
+            .unwrap_err();
+
+        let errors: Vec<(usize, OrderError)> =
+            errors.into_iter().map(|e| (e.line, e.error)).collect();
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    1,
+                    GameError::NotPlanetOwner {
+                        planet: PlanetId(2),
+                        race,
+                    }
+                    .into()
+                ),
+                (3, GameError::UnknownShip(ShipId(9)).into()),
+                (
+                    4,
+                    GameError::UnknownShipType("Battleship".to_string()).into()
+                ),
+                (5, OrderError::UnknownCommand("bogus".to_string())),
+                (6, GameError::SelfRelationship(race).into()),
+            ]
+        );
+        // The valid order in between still went through
+        assert_eq!(
+            game.galaxy()
+                .get_planet(PlanetId(0))
+                .unwrap()
+                .production_type(),
+            ProductionType::Capital
+        );
+        assert_eq!(
+            game.galaxy()
+                .get_planet(PlanetId(2))
+                .unwrap()
+                .production_type(),
+            ProductionType::Materials
+        );
+    }
+
+    #[test]
+    fn test_cannot_order_other_races_ships() {
+        let (mut game, race) = game_with_race();
+        game.galaxy_mut()
+            .get_planet_mut(PlanetId(2))
+            .unwrap()
+            .add_materials(100.0);
+        let enemy_ship = game
+            .build_ship(PlanetId(2), ShipDesign::new(1.0, 0, 0.0, 0.0, 0.0))
+            .unwrap();
+
+        let errors = game
+            .apply_orders(race, &format!("s {} 0", enemy_ship.0))
+            .unwrap_err();
+
+        assert_eq!(
+            errors[0].error,
+            OrderError::Rejected(GameError::NotShipOwner {
+                ship: enemy_ship,
+                race,
+            })
+        );
+        // Refusals end up in the race's turn events
+        assert!(game.advance_turn().contains(&TurnEvent::OrderRejected {
+            race,
+            error: GameError::NotShipOwner {
+                ship: enemy_ship,
+                race,
+            },
+        }));
+        assert!(!game.get_ship(enemy_ship).unwrap().location().is_traveling());
+    }
+}
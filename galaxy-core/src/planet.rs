@@ -1,11 +1,36 @@
+use std::collections::BTreeSet;
 use std::fmt;
 
-use bevy::prelude::*;
+#[cfg(feature = "bevy")]
+use bevy::prelude::Component;
+use serde::Deserialize;
+use serde::Serialize;
 
+use crate::error::GameError;
 use crate::race::TechnologyType;
+use crate::ship::CargoType;
+use crate::ship::ShipDesign;
+
+/// Production needed per unit of ship mass, on top of its materials
+pub const SHIP_PRODUCTION_PER_MASS: f64 = 10.0;
+
+/// Share of a ship's material cost recovered when it is scrapped
+pub const SCRAP_RECOVERY: f64 = 0.5;
+
+/// Population one colonist (COL) turns into when it lands
+pub const POPULATION_PER_COLONIST: f64 = 8.0;
+
+/// Mass of guns, and of shields, a planet's defences have per unit of
+/// industry
+pub const DEFENCE_PER_INDUSTRY: f64 = 0.1;
+
+/// Share of its full defences a planet rebuilds every turn after they were
+/// knocked out in battle
+pub const DEFENCE_REBUILD_PER_TURN: f64 = 0.25;
 
 /// Unique identifier for a planet
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Component)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(Component))]
 pub struct PlanetId(pub u32);
 
 impl fmt::Display for PlanetId {
@@ -15,7 +40,8 @@ impl fmt::Display for PlanetId {
 }
 
 /// Position of a planet in 2D space
-#[derive(Debug, Clone, Copy, PartialEq, Component)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(Component))]
 pub struct Position {
     x: f64,
     y: f64,
@@ -42,20 +68,34 @@ impl Position {
 }
 
 /// A planet in the galaxy
-#[derive(Debug, Clone, Component)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(Component))]
 pub struct Planet {
     id: PlanetId,
     position: Position,
     size: u32,
     owner: Option<u32>, // Race ID
+    /// Race that held the planet before it was left unowned
+    previous_owner: Option<u32>,
     population: f64,
     industry: f64,
     resources: f64,
     materials: f64,
     capital: f64,
     colonists: f64,
-    tech_focus: TechFocus,
     production_type: ProductionType,
+    /// Production already spent on the ship under construction
+    #[serde(default)]
+    ship_progress: f64,
+    /// Production already used this turn (e.g. for ship upgrades)
+    #[serde(default)]
+    production_spent: f64,
+    /// Races whose ships have visited the planet
+    #[serde(default)]
+    explored_by: BTreeSet<u32>,
+    /// Share of the planet's defences standing, rebuilt a little every turn
+    /// after they are knocked out
+    defence_strength: f64,
 }
 
 impl Planet {
@@ -65,14 +105,18 @@ impl Planet {
             position,
             size,
             owner,
+            previous_owner: None,
             population: 0.0,
             industry: 0.0,
             resources: 1.0,
             materials: 0.0,
             capital: 0.0,
             colonists: 0.0,
-            tech_focus: TechFocus::None,
             production_type: ProductionType::None,
+            ship_progress: 0.0,
+            production_spent: 0.0,
+            explored_by: BTreeSet::new(),
+            defence_strength: 1.0,
         }
     }
 
@@ -84,14 +128,18 @@ impl Planet {
             position,
             size,
             owner: Some(owner),
+            previous_owner: None,
             population: size_f,
             industry: size_f,
             resources: 10.0,
             materials: 0.0,
             capital: 0.0,
             colonists: 0.0,
-            tech_focus: TechFocus::None,
             production_type: ProductionType::Materials,
+            ship_progress: 0.0,
+            production_spent: 0.0,
+            explored_by: BTreeSet::from([owner]),
+            defence_strength: 1.0,
         }
     }
 
@@ -111,10 +159,54 @@ impl Planet {
         self.owner
     }
 
+    /// Race that held the planet before it was left unowned, until it is
+    /// settled again
+    pub fn previous_owner(&self) -> Option<u32> {
+        self.previous_owner
+    }
+
+    /// Hand the planet to a race, or leave it unowned
+    ///
+    /// A new owner starts with no production set and loses any ship the
+    /// previous owner had under construction.
     pub fn set_owner(&mut self, owner: Option<u32>) {
+        if self.owner == owner {
+            return;
+        }
+        self.production_type = ProductionType::None;
+        self.ship_progress = 0.0;
+        self.production_spent = 0.0;
+        self.previous_owner = match owner {
+            Some(_) => None,
+            None => self.owner,
+        };
         self.owner = owner;
     }
 
+    /// Settle an unowned planet with colonists unloaded by a race
+    ///
+    /// Each colonist becomes 8 population, joining whatever population
+    /// survived a bombing; colonists that do not fit on the planet stay in
+    /// its stockpile. The planet changes hands as with [`Planet::set_owner`].
+    pub fn colonize(&mut self, owner: u32, colonists: f64) {
+        let population = self.population + colonists * POPULATION_PER_COLONIST;
+        let max_population = self.size as f64;
+
+        self.set_owner(Some(owner));
+        self.explored_by.insert(owner);
+        self.population = population.min(max_population);
+        self.colonists += (population - self.population) / POPULATION_PER_COLONIST;
+    }
+
+    /// Remember that a race's ship has been here
+    pub fn explore(&mut self, race: u32) {
+        self.explored_by.insert(race);
+    }
+
+    pub fn is_explored_by(&self, race: u32) -> bool {
+        self.explored_by.contains(&race)
+    }
+
     pub fn materials(&self) -> f64 {
         self.materials
     }
@@ -143,17 +235,31 @@ impl Planet {
         self.resources = resources;
     }
 
-    pub fn tech_focus(&self) -> TechFocus {
-        self.tech_focus
+    /// Calculate production capacity: Industry + (Population - Industry)/4
+    pub fn production(&self) -> f64 {
+        self.industry + (self.population - self.industry) / 4.0
     }
 
-    pub fn set_tech_focus(&mut self, focus: TechFocus) {
-        self.tech_focus = focus;
+    /// Production not yet used up this turn
+    pub fn available_production(&self) -> f64 {
+        (self.production() - self.production_spent).max(0.0)
     }
 
-    /// Calculate production capacity: Industry + (Population - Industry)/4
-    pub fn production(&self) -> f64 {
-        self.industry + (self.population - self.industry) / 4.0
+    /// Use part of this turn's production outside the production queue
+    ///
+    /// Whatever is spent is missing from the planet's regular production at
+    /// the end of the turn.
+    pub fn spend_production(&mut self, amount: f64) -> Result<(), GameError> {
+        let available = self.available_production();
+        if amount > available {
+            return Err(GameError::InsufficientProduction {
+                planet: self.id,
+                needed: amount,
+                available,
+            });
+        }
+        self.production_spent += amount;
+        Ok(())
     }
 
     /// Grow population by 8% per turn, capped by planet size
@@ -171,7 +277,7 @@ impl Planet {
         } else {
             // Excess population becomes colonists (8 population = 1 colonist)
             let excess = new_population - max_population;
-            self.colonists += excess / 8.0;
+            self.colonists += excess / POPULATION_PER_COLONIST;
             self.population = max_population;
         }
 
@@ -211,30 +317,101 @@ impl Planet {
     }
 
     /// Consume materials for ship construction or capital production
-    pub fn consume_materials(&mut self, amount: f64) -> bool {
+    pub fn consume_materials(&mut self, amount: f64) -> Result<(), GameError> {
         if self.materials >= amount {
             self.materials -= amount;
-            true
+            Ok(())
         } else {
-            false
+            Err(self.shortage(CargoType::Materials, amount, self.materials))
         }
     }
 
     /// Consume capital
-    pub fn consume_capital(&mut self, amount: f64) -> bool {
+    pub fn consume_capital(&mut self, amount: f64) -> Result<(), GameError> {
         if self.capital >= amount {
             self.capital -= amount;
-            true
+            Ok(())
+        } else {
+            Err(self.shortage(CargoType::Capital, amount, self.capital))
+        }
+    }
+
+    /// Consume colonists, e.g. when they board a ship
+    pub fn consume_colonists(&mut self, amount: f64) -> Result<(), GameError> {
+        if self.colonists >= amount {
+            self.colonists -= amount;
+            Ok(())
         } else {
-            false
+            Err(self.shortage(CargoType::Colonists, amount, self.colonists))
+        }
+    }
+
+    fn shortage(&self, resource: CargoType, needed: f64, available: f64) -> GameError {
+        GameError::InsufficientStockpile {
+            planet: self.id,
+            resource,
+            needed,
+            available,
         }
     }
 
     /// Set production type
+    ///
+    /// Switching away from a ship type loses the progress on its current ship,
+    /// as in GalaxyNG.
     pub fn set_production_type(&mut self, production_type: ProductionType) {
+        if production_type != self.production_type {
+            self.ship_progress = 0.0;
+        }
         self.production_type = production_type;
     }
 
+    /// Production already spent on the ship under construction
+    pub fn ship_progress(&self) -> f64 {
+        self.ship_progress
+    }
+
+    /// Spend this turn's production on ships of the given design
+    ///
+    /// GalaxyNG costs: a ship needs 10 production and 1 material per unit of
+    /// mass. Materials come from the stockpile first; any shortfall is
+    /// produced on the spot at `1 / resources` production each. Production
+    /// left over after the last completed ship carries over to the next
+    /// turn. Returns the number of ships completed.
+    pub fn produce_ships(&mut self, design: &ShipDesign) -> u32 {
+        let mass = design.ship_mass();
+        if self.owner.is_none() || mass <= 0.0 {
+            return 0;
+        }
+
+        self.ship_progress += self.available_production();
+        self.production_spent = 0.0;
+
+        let mut built = 0;
+        loop {
+            let from_stockpile = self.materials.min(mass);
+            let shortfall = mass - from_stockpile;
+            if shortfall > 0.0 && self.resources <= 0.0 {
+                break;
+            }
+            let material_cost = if shortfall > 0.0 {
+                shortfall / self.resources
+            } else {
+                0.0
+            };
+            let cost = SHIP_PRODUCTION_PER_MASS * mass + material_cost;
+            if self.ship_progress < cost {
+                break;
+            }
+
+            self.ship_progress -= cost;
+            self.materials -= from_stockpile;
+            built += 1;
+        }
+
+        built
+    }
+
     /// Get current production type
     pub fn production_type(&self) -> ProductionType {
         self.production_type
@@ -245,13 +422,17 @@ impl Planet {
     /// - Materials: production × resources
     /// - Capital: 1 capital requires 5 production + 1 material (auto-diverts
     ///   production to materials if needed)
-    pub fn execute_production(&mut self) {
-        if self.owner.is_none() {
-            return;
+    /// - Research: all production becomes research effort, which is returned so
+    ///   it can be credited to the owning race
+    pub fn execute_production(&mut self) -> Option<(TechnologyType, f64)> {
+        self.owner?;
+
+        let prod = self.available_production();
+        if !matches!(self.production_type, ProductionType::Ships(_)) {
+            // Ship production is used up later, in produce_ships
+            self.production_spent = 0.0;
         }
 
-        let prod = self.production();
-
         match self.production_type {
             ProductionType::None => {}
             ProductionType::Materials => {
@@ -302,16 +483,44 @@ impl Planet {
                     }
                 }
             }
-            ProductionType::Research(_tech_type) => {
-                // TODO: Implement technology research
-                // For now, just do nothing (will be added in separate ticket)
+            ProductionType::Research(tech_type) => {
+                return Some((tech_type, prod));
             }
-            ProductionType::Ships(_ship_type_id) => {
-                // TODO: Implement ship building with material costs
-                // For now, just do nothing (will be integrated with ship
-                // system)
+            ProductionType::Ships(_) => {
+                // Needs the owner's design, so GameState calls produce_ships
             }
         }
+
+        None
+    }
+
+    /// Design of the guns and shields defending the planet in battles
+    ///
+    /// Defences grow with industry: an immobile battery with one attack and
+    /// `industry × DEFENCE_PER_INDUSTRY` of both weapons and shields, fighting
+    /// with the owner's tech, scaled down while they are being rebuilt.
+    /// Unowned planets, planets without industry and planets whose defences
+    /// were just knocked out have none.
+    pub fn defences(&self) -> Option<ShipDesign> {
+        self.owner?;
+        let mass = self.industry * DEFENCE_PER_INDUSTRY * self.defence_strength;
+        (mass > 0.0).then(|| ShipDesign::new(0.0, 1, mass, mass, 0.0))
+    }
+
+    /// Share of the planet's defences standing, from 0 right after they
+    /// were knocked out to 1 at full strength
+    pub fn defence_strength(&self) -> f64 {
+        self.defence_strength
+    }
+
+    /// Knock out the planet's defences after they were destroyed in battle
+    pub fn destroy_defences(&mut self) {
+        self.defence_strength = 0.0;
+    }
+
+    /// Rebuild [`DEFENCE_REBUILD_PER_TURN`] of the planet's defences
+    pub fn rebuild_defences(&mut self) {
+        self.defence_strength = (self.defence_strength + DEFENCE_REBUILD_PER_TURN).min(1.0);
     }
 
     /// Bomb the planet, reducing population and industry by 75%
@@ -323,8 +532,8 @@ impl Planet {
 }
 
 /// Production type for a planet
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
-
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(Component))]
 pub enum ProductionType {
     None,
     Materials,
@@ -333,23 +542,140 @@ pub enum ProductionType {
     Ships(ShipTypeId),
 }
 
-/// Temporary ID for ship types (will be replaced with proper ship type system
-/// later)
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Identifier of a ship type designed by a race
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct ShipTypeId(pub u32);
 
-/// Technology focus for a planet
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
-
-pub enum TechFocus {
-    None,
-    Research(TechnologyType),
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_consume_reports_shortage() {
+        let mut planet = Planet::new_home_planet(PlanetId(3), Position::new(0.0, 0.0), 100, 0);
+        planet.add_capital(2.0);
+
+        assert_eq!(planet.consume_capital(1.5), Ok(()));
+        assert_eq!(
+            planet.consume_capital(1.0),
+            Err(GameError::InsufficientStockpile {
+                planet: PlanetId(3),
+                resource: CargoType::Capital,
+                needed: 1.0,
+                available: 0.5,
+            })
+        );
+        assert_eq!(planet.capital(), 0.5);
+    }
+
+    #[test]
+    fn test_defences_grow_with_industry() {
+        let mut planet = Planet::new_home_planet(PlanetId(1), Position::new(0.0, 0.0), 100, 0);
+        let defences = planet.defences().unwrap();
+        assert_eq!(defences.weapons_mass(), 10.0);
+        assert_eq!(defences.shields_mass(), 10.0);
+        assert_eq!(defences.drive_mass(), 0.0);
+
+        planet.bomb();
+        assert_eq!(planet.defences().unwrap().weapons_mass(), 2.5);
+
+        let unowned = Planet::new(PlanetId(2), Position::new(0.0, 0.0), 100, None);
+        assert_eq!(unowned.defences(), None);
+    }
+
+    #[test]
+    fn test_destroyed_defences_are_rebuilt() {
+        let mut planet = Planet::new_home_planet(PlanetId(1), Position::new(0.0, 0.0), 100, 0);
+        planet.destroy_defences();
+        assert_eq!(planet.defences(), None);
+
+        planet.rebuild_defences();
+        assert_eq!(planet.defences().unwrap().weapons_mass(), 2.5);
+        for _ in 0..5 {
+            planet.rebuild_defences();
+        }
+        assert_eq!(planet.defence_strength(), 1.0);
+        assert_eq!(planet.defences().unwrap().weapons_mass(), 10.0);
+    }
+
+    #[test]
+    fn test_research_production() {
+        let mut planet = Planet::new_home_planet(PlanetId(1), Position::new(0.0, 0.0), 500, 0);
+        planet.set_production_type(ProductionType::Research(TechnologyType::Shield));
+
+        // All production goes into research; nothing is stockpiled
+        let research = planet.execute_production();
+        assert_eq!(research, Some((TechnologyType::Shield, 500.0)));
+        assert_eq!(planet.materials(), 0.0);
+
+        planet.set_production_type(ProductionType::Materials);
+        assert_eq!(planet.execute_production(), None);
+    }
+
+    #[test]
+    fn test_ship_production_carries_over() {
+        let mut planet = Planet::new_home_planet(PlanetId(1), Position::new(0.0, 0.0), 100, 0);
+        planet.set_production_type(ProductionType::Ships(ShipTypeId(0)));
+        // Mass 10: 100 production + 10 materials made at resources 10 (1 production)
+        let design = ShipDesign::new(10.0, 0, 0.0, 0.0, 0.0);
+
+        assert_eq!(planet.produce_ships(&design), 0);
+        assert_eq!(planet.ship_progress(), 100.0);
+
+        assert_eq!(planet.produce_ships(&design), 1);
+        assert!((planet.ship_progress() - 99.0).abs() < 1e-9);
+
+        // Switching production loses the partial ship
+        planet.set_production_type(ProductionType::Capital);
+        assert_eq!(planet.ship_progress(), 0.0);
+    }
+
+    #[test]
+    fn test_ship_production_uses_stockpile_and_builds_several() {
+        let mut planet = Planet::new_home_planet(PlanetId(1), Position::new(0.0, 0.0), 100, 0);
+        planet.add_materials(5.0);
+        // Mass 2: 20 production each, materials from the stockpile
+        let design = ShipDesign::new(1.0, 0, 0.0, 1.0, 0.0);
+
+        assert_eq!(planet.produce_ships(&design), 4);
+        assert!((planet.ship_progress() - 19.7).abs() < 1e-9);
+        assert_eq!(planet.materials(), 0.0);
+    }
+
+    #[test]
+    fn test_spent_production_is_missing_at_turn_end() {
+        let mut planet = Planet::new_home_planet(PlanetId(1), Position::new(0.0, 0.0), 100, 0);
+
+        planet.spend_production(30.0).unwrap();
+        assert_eq!(
+            planet.spend_production(80.0),
+            Err(GameError::InsufficientProduction {
+                planet: PlanetId(1),
+                needed: 80.0,
+                available: 70.0,
+            })
+        );
+        planet.execute_production();
+
+        // 70 production left at resources 10
+        assert_eq!(planet.materials(), 700.0);
+        assert_eq!(planet.available_production(), 100.0);
+    }
+
+    #[test]
+    fn test_colonize() {
+        let mut planet = Planet::new(PlanetId(2), Position::new(0.0, 0.0), 20, None);
+        assert!(!planet.is_explored_by(1));
+
+        planet.colonize(1, 3.0);
+
+        // 24 population does not fit on a size 20 planet
+        assert_eq!(planet.owner(), Some(1));
+        assert_eq!(planet.population(), 20.0);
+        assert_eq!(planet.colonists(), 0.5);
+        assert!(planet.is_explored_by(1));
+    }
+
     #[test]
     fn test_population_growth() {
         let mut planet = Planet::new_home_planet(
@@ -503,4 +829,5 @@ fn test_planet_ownership_change() {
 
     planet.set_owner(None);
     assert!(planet.owner().is_none());
+    assert_eq!(planet.previous_owner(), Some(1));
 }
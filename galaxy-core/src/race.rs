@@ -1,9 +1,21 @@
+use std::collections::BTreeMap;
 use std::fmt;
 
-use bevy::prelude::*;
+#[cfg(feature = "bevy")]
+use bevy::prelude::Component;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::error::GameError;
+use crate::planet::PlanetId;
+use crate::planet::ShipTypeId;
+use crate::route::Route;
+use crate::ship::CargoType;
+use crate::ship::ShipDesign;
 
 /// Unique identifier for a race
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Component)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(Component))]
 pub struct RaceId(pub u32);
 
 impl fmt::Display for RaceId {
@@ -13,7 +25,8 @@ impl fmt::Display for RaceId {
 }
 
 /// Technology types that can be advanced
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(Component))]
 pub enum TechnologyType {
     Drive,
     Weapon,
@@ -21,8 +34,13 @@ pub enum TechnologyType {
     Cargo,
 }
 
+/// Research effort (production spent on research) needed per technology level,
+/// as in GalaxyNG
+pub const RESEARCH_PER_LEVEL: f64 = 5000.0;
+
 /// Technology levels for a race
-#[derive(Debug, Clone, Component)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(Component))]
 pub struct Technology {
     drive_level: u32,
     weapon_level: u32,
@@ -73,11 +91,6 @@ impl Technology {
             TechnologyType::Cargo => self.cargo_level += 1,
         }
     }
-
-    /// Calculate effort required to advance technology based on planet size
-    pub fn effort_required(planet_size: u32, current_level: u32) -> f64 {
-        (planet_size as f64) * (current_level as f64)
-    }
 }
 
 impl Default for Technology {
@@ -87,7 +100,8 @@ impl Default for Technology {
 }
 
 /// A race in the galaxy
-#[derive(Debug, Clone, Component)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(Component))]
 pub struct Race {
     id: RaceId,
     name: String,
@@ -95,6 +109,13 @@ pub struct Race {
     home_planet_id: u32,
     tech_progress: TechProgress,
     ai_controlled: bool,
+    #[serde(default)]
+    ship_types: BTreeMap<ShipTypeId, ShipType>,
+    #[serde(default)]
+    next_ship_type_id: u32,
+    /// Standing cargo routes, at most one per origin and cargo
+    #[serde(default)]
+    routes: Vec<Route>,
 }
 
 impl Race {
@@ -106,6 +127,9 @@ impl Race {
             home_planet_id,
             tech_progress: TechProgress::new(),
             ai_controlled: false, // Human-controlled by default
+            ship_types: BTreeMap::new(),
+            next_ship_type_id: 0,
+            routes: Vec::new(),
         }
     }
 
@@ -117,6 +141,9 @@ impl Race {
             home_planet_id,
             tech_progress: TechProgress::new(),
             ai_controlled: true,
+            ship_types: BTreeMap::new(),
+            next_ship_type_id: 0,
+            routes: Vec::new(),
         }
     }
 
@@ -140,23 +167,109 @@ impl Race {
         self.home_planet_id
     }
 
+    /// Register a named ship design
+    pub fn add_ship_type(
+        &mut self,
+        name: String,
+        design: ShipDesign,
+    ) -> Result<ShipTypeId, GameError> {
+        design.validate()?;
+        if self.ship_type_by_name(&name).is_some() {
+            return Err(GameError::DuplicateShipType(name));
+        }
+
+        let id = ShipTypeId(self.next_ship_type_id);
+        self.next_ship_type_id += 1;
+        self.ship_types.insert(id, ShipType { name, design });
+        Ok(id)
+    }
+
+    pub fn ship_type(&self, id: ShipTypeId) -> Option<&ShipType> {
+        self.ship_types.get(&id)
+    }
+
+    /// All ship types of this race, in the order they were designed
+    pub fn ship_types(&self) -> impl Iterator<Item = (ShipTypeId, &ShipType)> {
+        self.ship_types
+            .iter()
+            .map(|(id, ship_type)| (*id, ship_type))
+    }
+
+    /// Forget a ship type; [`GameState::delete_ship_type`] checks that it is
+    /// unused first
+    ///
+    /// [`GameState::delete_ship_type`]: crate::game_state::GameState::delete_ship_type
+    pub(crate) fn remove_ship_type(&mut self, id: ShipTypeId) -> Option<ShipType> {
+        self.ship_types.remove(&id)
+    }
+
+    /// Look up a ship type by name (case-insensitive, like GalaxyNG)
+    pub fn ship_type_by_name(&self, name: &str) -> Option<ShipTypeId> {
+        self.ship_types
+            .iter()
+            .find(|(_, ship_type)| ship_type.name.eq_ignore_ascii_case(name))
+            .map(|(id, _)| *id)
+    }
+
+    pub fn routes(&self) -> &[Route] {
+        &self.routes
+    }
+
+    /// Add a route, replacing the one for the same origin and cargo
+    pub(crate) fn set_route(&mut self, route: Route) {
+        self.clear_route(route.origin, route.cargo);
+        self.routes.push(route);
+    }
+
+    pub(crate) fn clear_route(&mut self, origin: PlanetId, cargo: Option<CargoType>) {
+        self.routes
+            .retain(|route| route.origin != origin || route.cargo != cargo);
+    }
+
     /// Add research effort to a technology type
-    pub fn add_research(&mut self, tech_type: TechnologyType, effort: f64) {
+    ///
+    /// Every [`RESEARCH_PER_LEVEL`] effort advances the technology one level;
+    /// effort beyond that carries over. Returns true if the technology
+    /// advanced.
+    pub fn add_research(&mut self, tech_type: TechnologyType, effort: f64) -> bool {
         self.tech_progress.add_effort(tech_type, effort);
 
-        // Check if we can advance the technology
-        let current_level = self.technology.get_level(tech_type);
-        let required = Technology::effort_required(100, current_level); // Base calculation
-
-        if self.tech_progress.get_effort(tech_type) >= required {
+        let mut advanced = false;
+        while self.tech_progress.get_effort(tech_type) >= RESEARCH_PER_LEVEL {
             self.technology.advance(tech_type);
-            self.tech_progress.reset(tech_type);
+            self.tech_progress
+                .add_effort(tech_type, -RESEARCH_PER_LEVEL);
+            advanced = true;
         }
+
+        advanced
+    }
+
+    /// Research effort put into a technology since its last level
+    pub fn research_progress(&self, tech_type: TechnologyType) -> f64 {
+        self.tech_progress.get_effort(tech_type)
+    }
+}
+
+/// A ship design registered under a name by a race
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShipType {
+    name: String,
+    design: ShipDesign,
+}
+
+impl ShipType {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn design(&self) -> &ShipDesign {
+        &self.design
     }
 }
 
 /// Tracks research progress toward next technology level
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct TechProgress {
     drive_progress: f64,
     weapon_progress: f64,
@@ -191,13 +304,4 @@ impl TechProgress {
             TechnologyType::Cargo => self.cargo_progress,
         }
     }
-
-    fn reset(&mut self, tech_type: TechnologyType) {
-        match tech_type {
-            TechnologyType::Drive => self.drive_progress = 0.0,
-            TechnologyType::Weapon => self.weapon_progress = 0.0,
-            TechnologyType::Shield => self.shield_progress = 0.0,
-            TechnologyType::Cargo => self.cargo_progress = 0.0,
-        }
-    }
 }
@@ -1,4 +1,7 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use serde::Serialize;
 
 use crate::galaxy::Galaxy;
 use crate::planet::Planet;
@@ -6,13 +9,14 @@ use crate::planet::PlanetId;
 use crate::planet::ProductionType;
 use crate::race::Race;
 use crate::race::RaceId;
+use crate::ship::CargoType;
 use crate::ship::Ship;
 use crate::ship::ShipDesign;
 use crate::ship::ShipId;
 use crate::ship::ShipLocation;
 
 /// Behavioral personality for AI decision making
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Personality {
     /// Aggressive: Builds warships, seeks combat, attacks readily
     Aggressive,
@@ -88,6 +92,17 @@ impl Personality {
         }
     }
 
+    /// Name of the ship type built from [`Self::design_ship`]
+    fn ship_type_name(&self) -> &'static str {
+        match self {
+            Self::Aggressive => "Warship",
+            Self::Defensive => "Guardian",
+            Self::Expansionist => "Scout",
+            Self::Economic => "Colonist",
+            Self::Balanced => "Cruiser",
+        }
+    }
+
     /// Should aggressively colonize?
     fn colonization_priority(&self) -> f64 {
         match self {
@@ -130,7 +145,7 @@ impl Racebot {
         &self,
         galaxy: &Galaxy,
         race: &Race,
-        ships: &HashMap<ShipId, Ship>,
+        ships: &BTreeMap<ShipId, Ship>,
     ) -> RacebotDecisions {
         let mut decisions = RacebotDecisions::default();
 
@@ -148,8 +163,12 @@ impl Racebot {
         // Make ship building decisions
         decisions.ship_builds = self.decide_ship_builds(&state, race);
 
-        // Make ship movement decisions
-        decisions.ship_movements = self.decide_ship_movements(&state, ships, galaxy);
+        // Load and land colonists before ships move on
+        decisions.cargo_orders = self.decide_cargo(&state, ships, galaxy);
+
+        // Make ship movement decisions; ships leaving together form a fleet
+        let movements = self.decide_ship_movements(&state, ships, galaxy);
+        (decisions.ship_movements, decisions.fleet_movements) = form_fleets(movements, ships);
 
         decisions
     }
@@ -159,7 +178,7 @@ impl Racebot {
         &self,
         galaxy: &Galaxy,
         _race: &Race,
-        ships: &HashMap<ShipId, Ship>,
+        ships: &BTreeMap<ShipId, Ship>,
     ) -> GameState {
         let mut state = GameState::default();
 
@@ -264,18 +283,63 @@ impl Racebot {
             builds.push(ShipBuild {
                 planet_id: *planet_id,
                 design: ship_design,
-                name: format!("{:?}-{}", self.personality, planet_id.0),
+                name: self.personality.ship_type_name().to_string(),
             });
         }
 
         builds
     }
 
+    /// Decide which ships pick up or land colonists
+    ///
+    /// Ships with cargo space take colonists from our planets along, and land
+    /// them on the first unowned planet they reach.
+    fn decide_cargo(
+        &self,
+        state: &GameState,
+        ships: &BTreeMap<ShipId, Ship>,
+        galaxy: &Galaxy,
+    ) -> Vec<CargoOrder> {
+        let mut orders = Vec::new();
+
+        for ship_id in &state.owned_ships {
+            let Some(ship) = ships.get(ship_id) else {
+                continue;
+            };
+            let Some(planet) = ship
+                .location()
+                .planet_id()
+                .and_then(|id| galaxy.get_planet(id))
+            else {
+                continue;
+            };
+
+            let carrying = ship.cargo(CargoType::Colonists) > 0.0;
+            if carrying && planet.owner().is_none() {
+                orders.push(CargoOrder::Unload {
+                    ship_id: *ship_id,
+                    cargo: CargoType::Colonists,
+                });
+            } else if !carrying
+                && ship.design().cargo_mass() > 0.0
+                && planet.owner() == Some(self.race_id.0)
+                && planet.colonists() > 0.0
+            {
+                orders.push(CargoOrder::Load {
+                    ship_id: *ship_id,
+                    cargo: CargoType::Colonists,
+                });
+            }
+        }
+
+        orders
+    }
+
     /// Decide where to move ships
     fn decide_ship_movements(
         &self,
         state: &GameState,
-        ships: &HashMap<ShipId, Ship>,
+        ships: &BTreeMap<ShipId, Ship>,
         galaxy: &Galaxy,
     ) -> Vec<ShipMovement> {
         let mut movements = Vec::new();
@@ -336,6 +400,7 @@ impl Racebot {
         state
             .colonizable_planets
             .iter()
+            .filter(|planet_id| **planet_id != from)
             .min_by_key(|planet_id| {
                 if let Some(planet) = galaxy.get_planet(**planet_id) {
                     let dx = planet.position().x() - from_pos.x();
@@ -349,6 +414,39 @@ impl Racebot {
     }
 }
 
+/// Gather ships sent from the same planet to the same destination into fleet
+/// movements
+fn form_fleets(
+    movements: Vec<ShipMovement>,
+    ships: &BTreeMap<ShipId, Ship>,
+) -> (Vec<ShipMovement>, Vec<FleetMovement>) {
+    let mut legs: BTreeMap<(Option<PlanetId>, PlanetId), Vec<ShipId>> = BTreeMap::new();
+    for movement in movements {
+        let origin = ships
+            .get(&movement.ship_id)
+            .and_then(|ship| ship.location().planet_id());
+        legs.entry((origin, movement.destination))
+            .or_default()
+            .push(movement.ship_id);
+    }
+
+    let mut single = Vec::new();
+    let mut fleets = Vec::new();
+    for ((_, destination), leg_ships) in legs {
+        match leg_ships.as_slice() {
+            [ship_id] => single.push(ShipMovement {
+                ship_id: *ship_id,
+                destination,
+            }),
+            _ => fleets.push(FleetMovement {
+                ships: leg_ships,
+                destination,
+            }),
+        }
+    }
+    (single, fleets)
+}
+
 /// Analyzed game state for decision making
 #[derive(Default)]
 struct GameState {
@@ -365,9 +463,11 @@ struct GameState {
 /// Decisions made by the racebot
 #[derive(Default, Debug)]
 pub struct RacebotDecisions {
-    pub production_orders: HashMap<PlanetId, ProductionType>,
+    pub production_orders: BTreeMap<PlanetId, ProductionType>,
     pub ship_builds: Vec<ShipBuild>,
+    pub cargo_orders: Vec<CargoOrder>,
     pub ship_movements: Vec<ShipMovement>,
+    pub fleet_movements: Vec<FleetMovement>,
 }
 
 /// Order to build a ship
@@ -375,10 +475,19 @@ pub struct RacebotDecisions {
 pub struct ShipBuild {
     pub planet_id: PlanetId,
     pub design: ShipDesign,
-
+    /// Ship type the design is registered as
     pub name: String,
 }
 
+/// Order to move cargo between a ship and the planet it orbits
+#[derive(Debug)]
+pub enum CargoOrder {
+    /// Fill the hold with as much as the planet has
+    Load { ship_id: ShipId, cargo: CargoType },
+    /// Unload everything of this type
+    Unload { ship_id: ShipId, cargo: CargoType },
+}
+
 /// Order to move a ship
 #[derive(Debug)]
 pub struct ShipMovement {
@@ -386,6 +495,13 @@ pub struct ShipMovement {
     pub destination: PlanetId,
 }
 
+/// Order to move ships waiting at one planet together as a new fleet
+#[derive(Debug)]
+pub struct FleetMovement {
+    pub ships: Vec<ShipId>,
+    pub destination: PlanetId,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -412,7 +528,7 @@ mod tests {
 
         // Analyze state
         let race = game.get_race(race_id).unwrap();
-        let ships = HashMap::new();
+        let ships = BTreeMap::new();
         let state = racebot.analyze_state(game.galaxy(), race, &ships);
 
         // Verify analysis
@@ -460,13 +576,13 @@ mod tests {
         // Build a scout ship
         let scout = ShipDesign::new(2.0, 0, 0.0, 1.0, 1.0);
         let ship_id = game.build_ship(home_planet, scout);
-        assert!(ship_id.is_some(), "Failed to build ship");
+        let ship_id = ship_id.expect("Failed to build ship");
 
         // Run racebot - it should send the ship to colonize
         game.run_racebot(race_id);
 
         // Check ship was ordered to move (or at least still exists)
-        let ship = game.get_ship(ship_id.unwrap()).unwrap();
+        let ship = game.get_ship(ship_id).unwrap();
         // Ship should either be traveling or still at home
         match ship.location() {
             ShipLocation::AtPlanet(_) | ShipLocation::Traveling { .. } => {}
@@ -492,7 +608,7 @@ mod tests {
 
         let racebot = Racebot::with_personality(race_id, Personality::Balanced);
         let race = game.get_race(race_id).unwrap();
-        let ships = HashMap::new();
+        let ships = BTreeMap::new();
         let state = racebot.analyze_state(game.galaxy(), race, &ships);
 
         // Find nearest from home
@@ -587,7 +703,7 @@ mod tests {
 
         let economic_bot = Racebot::with_personality(race_id, Personality::Economic);
         let race = game.get_race(race_id).unwrap();
-        let ships = HashMap::new();
+        let ships = BTreeMap::new();
         let state = economic_bot.analyze_state(game.galaxy(), race, &ships);
 
         let planet = game.galaxy().get_planet(home_planet).unwrap();
@@ -2,6 +2,7 @@ use bevy::input::mouse::MouseWheel;
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 
+use crate::event::TurnEvent;
 use crate::game_state::GameState;
 
 const BACKGROUND_COLOR: Color = Color::srgb(0.05, 0.05, 0.1);
@@ -319,6 +320,16 @@ fn update_ui(
                 "Races: {} | Planets: {} | Ships: {}",
                 races, total_planets, total_ships
             );
+            let rejected = game_state
+                .events()
+                .iter()
+                .filter(|e| matches!(e, TurnEvent::OrderRejected { .. }));
+            if let Some(last) = rejected.clone().next_back() {
+                text.push_str(&format!(
+                    " | Rejected orders: {} (last: {last})",
+                    rejected.count()
+                ));
+            }
         }
     }
 }
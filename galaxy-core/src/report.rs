@@ -0,0 +1,824 @@
+use std::fmt;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::combat::BattleReport;
+use crate::combat::Combatant;
+use crate::diplomacy::Relationship;
+use crate::error::GameError;
+use crate::event::TurnEvent;
+use crate::fleet::GroupId;
+use crate::game_state::GameState;
+use crate::planet::Planet;
+use crate::planet::PlanetId;
+use crate::planet::ProductionType;
+use crate::planet::ShipTypeId;
+use crate::race::RaceId;
+use crate::race::TechnologyType;
+use crate::route::Route;
+use crate::ship::CargoType;
+use crate::ship::ShipDesign;
+use crate::ship::ShipId;
+use crate::ship::ShipLocation;
+
+/// Everything a race knows after a turn, modeled on the GalaxyNG turn report
+///
+/// `Display` renders the plain-text report; the struct itself serializes to
+/// the machine-readable variant.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RaceReport {
+    pub race: RaceId,
+    pub name: String,
+    pub turn: u32,
+    pub technology: TechLevels,
+    pub relationships: Vec<RelationshipEntry>,
+    pub ship_types: Vec<ShipTypeEntry>,
+    pub planets: Vec<PlanetEntry>,
+    /// Own ships, grouped by ship type
+    pub ships: Vec<ShipEntry>,
+    pub routes: Vec<Route>,
+    pub fleets: Vec<FleetEntry>,
+    /// Shot-by-shot reports of the battles this race fought in during the
+    /// turn
+    pub battles: Vec<BattleReport>,
+    /// Bombings this race carried out or suffered during the turn
+    pub bombings: Vec<TurnEvent>,
+    /// Planets this race colonized, captured or lost to a capture during the
+    /// turn
+    pub planet_changes: Vec<TurnEvent>,
+    /// Planets of other races (or nobody) this race has explored or has ships
+    /// at
+    pub foreign_planets: Vec<ForeignPlanetEntry>,
+    /// Orders of this race the game refused
+    pub rejected_orders: Vec<GameError>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TechLevels {
+    pub drive: u32,
+    pub weapons: u32,
+    pub shields: u32,
+    pub cargo: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelationshipEntry {
+    pub race: RaceId,
+    pub name: String,
+    pub relationship: Relationship,
+}
+
+/// A ship type the race has designed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShipTypeEntry {
+    pub id: ShipTypeId,
+    pub name: String,
+    pub design: ShipDesign,
+}
+
+/// One of the race's own planets
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanetEntry {
+    pub id: PlanetId,
+    pub x: f64,
+    pub y: f64,
+    pub size: u32,
+    pub population: f64,
+    pub industry: f64,
+    pub resources: f64,
+    pub production: ProductionType,
+    pub capital: f64,
+    pub materials: f64,
+    pub colonists: f64,
+}
+
+/// One of the race's own ships
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShipEntry {
+    pub id: ShipId,
+    /// Name of the ship type the ship was built as
+    pub ship_type: Option<String>,
+    pub design: ShipDesign,
+    pub hull: f64,
+    pub location: ShipLocation,
+    pub colonists: f64,
+    pub materials: f64,
+    pub capital: f64,
+    /// The group the ship belongs to, if any
+    pub group: Option<GroupId>,
+    /// Name of the fleet the ship travels with, if any
+    pub fleet: Option<String>,
+}
+
+/// One of the race's fleets
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FleetEntry {
+    pub name: String,
+    pub groups: Vec<GroupId>,
+    pub ships: usize,
+    /// Where the fleet's first ship is
+    pub location: Option<ShipLocation>,
+    /// Speed of the slowest ship
+    pub speed: f64,
+}
+
+/// A planet the race does not own but can see
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForeignPlanetEntry {
+    pub id: PlanetId,
+    pub owner: Option<RaceId>,
+    pub x: f64,
+    pub y: f64,
+    pub size: u32,
+    pub population: f64,
+    pub industry: f64,
+    pub resources: f64,
+}
+
+impl RaceReport {
+    /// Build the report for a race from the current game state and the events
+    /// of the last turn
+    pub fn new(game: &GameState, race_id: RaceId) -> Option<Self> {
+        let race = game.get_race(race_id)?;
+        let tech = race.technology();
+
+        let relationships = game
+            .races()
+            .filter(|other| other.id() != race_id)
+            .map(|other| RelationshipEntry {
+                race: other.id(),
+                name: other.name().to_string(),
+                relationship: game.diplomacy().get_relationship(race_id, other.id()),
+            })
+            .collect();
+
+        let ship_types = race
+            .ship_types()
+            .map(|(id, ship_type)| ShipTypeEntry {
+                id,
+                name: ship_type.name().to_string(),
+                design: *ship_type.design(),
+            })
+            .collect();
+
+        let planets = game
+            .galaxy()
+            .planets_owned_by(race_id.0)
+            .map(PlanetEntry::new)
+            .collect();
+
+        let own_ships: Vec<_> = game.ships().filter(|s| s.owner() == race_id).collect();
+        let fleets = game.fleets();
+        let mut ships: Vec<ShipEntry> = own_ships
+            .iter()
+            .map(|ship| {
+                let group = fleets.group_of(ship.id());
+                ShipEntry {
+                    id: ship.id(),
+                    ship_type: ship
+                        .ship_type()
+                        .and_then(|id| race.ship_type(id))
+                        .map(|t| t.name().to_string()),
+                    design: *ship.design(),
+                    hull: ship.current_hull(),
+                    location: *ship.location(),
+                    colonists: ship.cargo(CargoType::Colonists),
+                    materials: ship.cargo(CargoType::Materials),
+                    capital: ship.cargo(CargoType::Capital),
+                    group: group.map(|g| g.id()),
+                    fleet: group
+                        .and_then(|g| g.fleet())
+                        .and_then(|id| fleets.get_fleet(id))
+                        .map(|f| f.name().to_string()),
+                }
+            })
+            .collect();
+        ships.sort_by(|a, b| a.ship_type.cmp(&b.ship_type).then(a.id.cmp(&b.id)));
+
+        let fleets = fleets
+            .fleets()
+            .filter(|fleet| fleet.owner() == race_id)
+            .map(|fleet| {
+                let fleet_ships: Vec<_> = fleets
+                    .fleet_ships(fleet.id())
+                    .into_iter()
+                    .filter_map(|id| game.get_ship(id))
+                    .collect();
+                FleetEntry {
+                    name: fleet.name().to_string(),
+                    groups: fleets.groups_in(fleet.id()).map(|g| g.id()).collect(),
+                    ships: fleet_ships.len(),
+                    location: fleet_ships.first().map(|s| *s.location()),
+                    speed: fleet_ships
+                        .iter()
+                        .map(|s| s.travel_speed())
+                        .fold(f64::INFINITY, f64::min),
+                }
+            })
+            .collect();
+
+        let foreign_planets = game
+            .galaxy()
+            .planets()
+            .filter(|p| p.owner() != Some(race_id.0))
+            .filter(|p| {
+                p.is_explored_by(race_id.0)
+                    || own_ships
+                        .iter()
+                        .any(|s| s.location().planet_id() == Some(p.id()))
+            })
+            .map(ForeignPlanetEntry::new)
+            .collect();
+
+        let involved = |event: &&TurnEvent| event.involves(race_id);
+        let battles = game
+            .battles()
+            .iter()
+            .filter(|battle| battle.races.contains(&race_id))
+            .cloned()
+            .collect();
+        let bombings = game
+            .events()
+            .iter()
+            .filter(involved)
+            .filter(|e| matches!(e, TurnEvent::PlanetBombed { .. }))
+            .cloned()
+            .collect();
+        let planet_changes = game
+            .events()
+            .iter()
+            .filter(involved)
+            .filter(|e| {
+                matches!(
+                    e,
+                    TurnEvent::PlanetColonized { .. } | TurnEvent::PlanetCaptured { .. }
+                )
+            })
+            .cloned()
+            .collect();
+        let rejected_orders = game
+            .events()
+            .iter()
+            .filter_map(|event| match event {
+                TurnEvent::OrderRejected { race, error } if *race == race_id => Some(error.clone()),
+                _ => None,
+            })
+            .collect();
+
+        Some(Self {
+            race: race_id,
+            name: race.name().to_string(),
+            turn: game.turn(),
+            technology: TechLevels {
+                drive: tech.drive_level(),
+                weapons: tech.weapon_level(),
+                shields: tech.shield_level(),
+                cargo: tech.cargo_level(),
+            },
+            relationships,
+            ship_types,
+            planets,
+            ships,
+            routes: race.routes().to_vec(),
+            fleets,
+            battles,
+            bombings,
+            planet_changes,
+            foreign_planets,
+            rejected_orders,
+        })
+    }
+
+    /// Machine-readable JSON variant of the report
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+impl PlanetEntry {
+    fn new(planet: &Planet) -> Self {
+        Self {
+            id: planet.id(),
+            x: planet.position().x(),
+            y: planet.position().y(),
+            size: planet.size(),
+            population: planet.population(),
+            industry: planet.industry(),
+            resources: planet.resources(),
+            production: planet.production_type(),
+            capital: planet.capital(),
+            materials: planet.materials(),
+            colonists: planet.colonists(),
+        }
+    }
+}
+
+impl ForeignPlanetEntry {
+    fn new(planet: &Planet) -> Self {
+        Self {
+            id: planet.id(),
+            owner: planet.owner().map(RaceId),
+            x: planet.position().x(),
+            y: planet.position().y(),
+            size: planet.size(),
+            population: planet.population(),
+            industry: planet.industry(),
+            resources: planet.resources(),
+        }
+    }
+}
+
+/// GalaxyNG-style name of what a planet is producing
+fn production_name(production: ProductionType, ship_types: &[ShipTypeEntry]) -> String {
+    match production {
+        ProductionType::None => "-".to_string(),
+        ProductionType::Materials => "MAT".to_string(),
+        ProductionType::Capital => "CAP".to_string(),
+        ProductionType::Research(tech) => match tech {
+            TechnologyType::Drive => "Drive",
+            TechnologyType::Weapon => "Weapons",
+            TechnologyType::Shield => "Shields",
+            TechnologyType::Cargo => "Cargo",
+        }
+        .to_string(),
+        ProductionType::Ships(ship_type) => ship_types
+            .iter()
+            .find(|entry| entry.id == ship_type)
+            .map_or_else(
+                || format!("Ship#{}", ship_type.0),
+                |entry| entry.name.clone(),
+            ),
+    }
+}
+
+fn route_cargo_name(cargo: Option<CargoType>) -> &'static str {
+    match cargo {
+        Some(CargoType::Colonists) => "COL",
+        Some(CargoType::Materials) => "MAT",
+        Some(CargoType::Capital) => "CAP",
+        None => "EMP",
+    }
+}
+
+fn combatant_text(combatant: Combatant) -> String {
+    match combatant {
+        Combatant::Ship(ship) => format!("ship {}", ship.0),
+        Combatant::Planet(planet) => format!("planet #{}", planet.0),
+    }
+}
+
+fn location_text(location: &ShipLocation) -> String {
+    match location {
+        ShipLocation::AtPlanet(planet) => format!("#{}", planet.0),
+        ShipLocation::Traveling { from, to, progress } => {
+            format!("#{} -> #{} ({:.0}%)", from.0, to.0, progress * 100.0)
+        }
+    }
+}
+
+impl fmt::Display for RaceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "\t\t{} Report for Galaxy Turn {}", self.name, self.turn)?;
+
+        if !self.rejected_orders.is_empty() {
+            writeln!(f, "\n\t\tRejected Orders\n")?;
+            for error in &self.rejected_orders {
+                writeln!(f, "{error}")?;
+            }
+        }
+
+        writeln!(f, "\n\t\tYour Tech Levels\n")?;
+        writeln!(f, "    D     W     S     C")?;
+        let tech = &self.technology;
+        writeln!(
+            f,
+            "{:>5} {:>5} {:>5} {:>5}",
+            tech.drive, tech.weapons, tech.shields, tech.cargo
+        )?;
+
+        if !self.relationships.is_empty() {
+            writeln!(f, "\n\t\tStatus of Races\n")?;
+            writeln!(f, "N                   # Status")?;
+            for entry in &self.relationships {
+                let status = match entry.relationship {
+                    Relationship::Friendly => "Peace",
+                    Relationship::Hostile => "War",
+                    Relationship::Neutral => "Neutral",
+                };
+                writeln!(f, "{:<16} {:>4} {}", entry.name, entry.race.0, status)?;
+            }
+        }
+
+        for battle in &self.battles {
+            writeln!(
+                f,
+                "\n\t\tBattle at #{} ({} rounds)\n",
+                battle.planet.0, battle.rounds
+            )?;
+            writeln!(f, "Round Attacker     Target       Result")?;
+            for shot in &battle.shots {
+                writeln!(
+                    f,
+                    "{:>5} {:<12} {:<12} {}",
+                    shot.round,
+                    combatant_text(shot.attacker),
+                    combatant_text(shot.target),
+                    if shot.destroyed {
+                        "Destroyed"
+                    } else {
+                        "Shields"
+                    }
+                )?;
+            }
+        }
+
+        if !self.bombings.is_empty() {
+            writeln!(f, "\n\t\tBombings\n")?;
+            for event in &self.bombings {
+                writeln!(f, "{event}")?;
+            }
+        }
+
+        if !self.planet_changes.is_empty() {
+            writeln!(f, "\n\t\tColonized and Captured Planets\n")?;
+            for event in &self.planet_changes {
+                writeln!(f, "{event}")?;
+            }
+        }
+
+        if !self.ship_types.is_empty() {
+            writeln!(f, "\n\t\tYour Ship Types\n")?;
+            writeln!(f, "N                     D   A      W      S      C   Mass")?;
+            for ship_type in &self.ship_types {
+                let design = &ship_type.design;
+                writeln!(
+                    f,
+                    "{:<16} {:>6.2} {:>3} {:>6.2} {:>6.2} {:>6.2} {:>6.2}",
+                    ship_type.name,
+                    design.drive_mass(),
+                    design.attacks(),
+                    design.weapons_mass(),
+                    design.shields_mass(),
+                    design.cargo_mass(),
+                    design.ship_mass()
+                )?;
+            }
+        }
+
+        writeln!(f, "\n\t\tYour Planets\n")?;
+        writeln!(
+            f,
+            "   N        X        Y      S        P        I      R        P        $        \
+             M      C"
+        )?;
+        for planet in &self.planets {
+            writeln!(
+                f,
+                "{:>4} {:>8.2} {:>8.2} {:>6} {:>8.2} {:>8.2} {:>6.2} {:>8} {:>8.2} {:>8.2} {:>6.2}",
+                planet.id.0,
+                planet.x,
+                planet.y,
+                planet.size,
+                planet.population,
+                planet.industry,
+                planet.resources,
+                production_name(planet.production, &self.ship_types),
+                planet.capital,
+                planet.materials,
+                planet.colonists
+            )?;
+        }
+
+        writeln!(f, "\n\t\tYour Ships\n")?;
+        writeln!(
+            f,
+            "   # T                     D   A      W      S      C   Hull Location                \
+             COL    MAT    CAP    G Fleet"
+        )?;
+        for ship in &self.ships {
+            let design = &ship.design;
+            writeln!(
+                f,
+                "{:>4} {:<16} {:>6.2} {:>3} {:>6.2} {:>6.2} {:>6.2} {:>6.2} {:<20} {:>6.2} \
+                 {:>6.2} {:>6.2} {:>4} {}",
+                ship.id.0,
+                ship.ship_type.as_deref().unwrap_or("-"),
+                design.drive_mass(),
+                design.attacks(),
+                design.weapons_mass(),
+                design.shields_mass(),
+                design.cargo_mass(),
+                ship.hull,
+                location_text(&ship.location),
+                ship.colonists,
+                ship.materials,
+                ship.capital,
+                ship.group.map_or("-".to_string(), |g| g.0.to_string()),
+                ship.fleet.as_deref().unwrap_or("-")
+            )?;
+        }
+
+        if !self.fleets.is_empty() {
+            writeln!(f, "\n\t\tYour Fleets\n")?;
+            writeln!(f, "Name             Groups  Ships  Speed Location")?;
+            for fleet in &self.fleets {
+                writeln!(
+                    f,
+                    "{:<16} {:>6} {:>6} {:>6.2} {}",
+                    fleet.name,
+                    fleet.groups.len(),
+                    fleet.ships,
+                    fleet.speed,
+                    fleet
+                        .location
+                        .as_ref()
+                        .map_or("-".to_string(), location_text)
+                )?;
+            }
+        }
+
+        if !self.routes.is_empty() {
+            writeln!(f, "\n\t\tYour Routes\n")?;
+            writeln!(f, "   N Cargo    To")?;
+            for route in &self.routes {
+                writeln!(
+                    f,
+                    "{:>4} {:<5} {:>5}",
+                    route.origin.0,
+                    route_cargo_name(route.cargo),
+                    route.destination.0
+                )?;
+            }
+        }
+
+        if !self.foreign_planets.is_empty() {
+            writeln!(f, "\n\t\tOther Planets\n")?;
+            writeln!(
+                f,
+                "   N  Owner        X        Y      S        P        I      R"
+            )?;
+            for planet in &self.foreign_planets {
+                let owner = planet.owner.map_or("-".to_string(), |o| o.0.to_string());
+                writeln!(
+                    f,
+                    "{:>4} {:>6} {:>8.2} {:>8.2} {:>6} {:>8.2} {:>8.2} {:>6.2}",
+                    planet.id.0,
+                    owner,
+                    planet.x,
+                    planet.y,
+                    planet.size,
+                    planet.population,
+                    planet.industry,
+                    planet.resources
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl GameState {
+    /// Turn report for a race, covering the most recent turn
+    pub fn race_report(&self, race_id: RaceId) -> Option<RaceReport> {
+        RaceReport::new(self, race_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::planet::Position;
+
+    fn two_race_game() -> (GameState, RaceId, RaceId, PlanetId) {
+        let mut game = GameState::new(1000.0, 1000.0);
+        let home1 = game
+            .galaxy_mut()
+            .add_planet(Position::new(100.0, 100.0), 100, Some(0));
+        let home2 = game
+            .galaxy_mut()
+            .add_planet(Position::new(900.0, 900.0), 80, Some(1));
+        let race1 = game.add_race("Humans".to_string(), home1.0);
+        let race2 = game.add_race("Zorgs".to_string(), home2.0);
+        (game, race1, race2, home2)
+    }
+
+    #[test]
+    fn test_report_lists_own_planets_and_ships() {
+        let (mut game, race1, race2, home2) = two_race_game();
+        game.set_relationship(race1, race2, Relationship::Hostile);
+        game.galaxy_mut()
+            .get_planet_mut(PlanetId(0))
+            .unwrap()
+            .add_materials(100.0);
+        let ship = game
+            .build_ship(PlanetId(0), ShipDesign::new(1.0, 0, 0.0, 0.0, 1.0))
+            .unwrap();
+        game.advance_turn();
+
+        let report = game.race_report(race1).unwrap();
+
+        assert_eq!(report.name, "Humans");
+        assert_eq!(report.turn, 1);
+        assert_eq!(report.planets.len(), 1);
+        assert_eq!(report.planets[0].id, PlanetId(0));
+        assert_eq!(report.ships.len(), 1);
+        assert_eq!(report.ships[0].id, ship);
+        assert_eq!(
+            report.relationships,
+            vec![RelationshipEntry {
+                race: race2,
+                name: "Zorgs".to_string(),
+                relationship: Relationship::Hostile,
+            }]
+        );
+        // The enemy home world is out of sight
+        assert!(report.foreign_planets.is_empty());
+        assert!(!report.foreign_planets.iter().any(|p| p.id == home2));
+    }
+
+    #[test]
+    fn test_ships_reveal_foreign_planets() {
+        let (mut game, race1, race2, _) = two_race_game();
+        let home1 = PlanetId(0);
+        game.galaxy_mut()
+            .get_planet_mut(home1)
+            .unwrap()
+            .add_materials(100.0);
+        game.build_ship(home1, ShipDesign::new(1.0, 0, 0.0, 0.0, 0.0))
+            .unwrap();
+        // The ship stays behind when the planet changes hands
+        game.galaxy_mut()
+            .get_planet_mut(home1)
+            .unwrap()
+            .set_owner(Some(race2.0));
+
+        let report = game.race_report(race1).unwrap();
+
+        assert!(report.planets.is_empty());
+        assert_eq!(report.foreign_planets.len(), 1);
+        assert_eq!(report.foreign_planets[0].id, home1);
+        assert_eq!(report.foreign_planets[0].owner, Some(race2));
+    }
+
+    #[test]
+    fn test_text_and_json_formats() {
+        let (mut game, race1, _, _) = two_race_game();
+        game.advance_turn();
+        let report = game.race_report(race1).unwrap();
+
+        let text = report.to_string();
+        assert!(text.contains("Humans Report for Galaxy Turn 1"));
+        assert!(text.contains("Your Planets"));
+        assert!(text.contains("Zorgs"));
+
+        let parsed: RaceReport = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(parsed, report);
+    }
+
+    #[test]
+    fn test_report_lists_battles_and_new_colonies() {
+        let (mut game, race1, race2, home2) = two_race_game();
+        let home1 = PlanetId(0);
+        let colony = game
+            .galaxy_mut()
+            .add_planet(Position::new(120.0, 100.0), 50, None);
+        let planet = game.galaxy_mut().get_planet_mut(home1).unwrap();
+        planet.add_materials(1000.0);
+        planet.add_colonists(1.0);
+        let dreadnought = game
+            .build_ship(home1, ShipDesign::new(1.0, 1, 200.0, 200.0, 0.0))
+            .unwrap();
+        let freighter = game
+            .build_ship(home1, ShipDesign::new(1.0, 0, 0.0, 0.0, 1.0))
+            .unwrap();
+        game.load_ship(freighter, CargoType::Colonists, Some(1.0))
+            .unwrap();
+        game.get_ship_mut(dreadnought)
+            .unwrap()
+            .set_location(ShipLocation::AtPlanet(home2));
+        game.get_ship_mut(freighter)
+            .unwrap()
+            .set_location(ShipLocation::AtPlanet(colony));
+        game.unload_ship(freighter, CargoType::Colonists, None)
+            .unwrap();
+        game.set_relationship(race1, race2, Relationship::Hostile);
+        game.advance_turn();
+
+        let report = game.race_report(race1).unwrap();
+
+        assert_eq!(report.battles.len(), 1);
+        assert_eq!(report.battles[0].planet, home2);
+        assert!(!report.battles[0].shots.is_empty());
+        assert_eq!(game.race_report(race2).unwrap().battles, report.battles);
+        assert_eq!(
+            report.planet_changes,
+            vec![TurnEvent::PlanetColonized {
+                planet: colony,
+                race: race1,
+            }]
+        );
+        let text = report.to_string();
+        assert!(text.contains(&format!("Battle at #{}", home2.0)));
+        assert!(text.contains(&format!("ship {}", dreadnought.0)));
+        assert!(text.contains("Colonized and Captured Planets"));
+        let parsed: RaceReport = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(parsed, report);
+    }
+
+    #[test]
+    fn test_report_lists_rejected_orders() {
+        let (mut game, race1, ..) = two_race_game();
+        let errors = game.apply_orders(race1, "p 1 CAP").unwrap_err();
+        game.advance_turn();
+
+        let report = game.race_report(race1).unwrap();
+
+        assert_eq!(
+            report.rejected_orders,
+            vec![GameError::NotPlanetOwner {
+                planet: PlanetId(1),
+                race: race1,
+            }]
+        );
+        assert_eq!(errors.len(), 1);
+        assert!(report.to_string().contains("Rejected Orders"));
+    }
+
+    #[test]
+    fn test_ships_grouped_by_type() {
+        let (mut game, race1, ..) = two_race_game();
+        let home1 = PlanetId(0);
+        game.galaxy_mut()
+            .get_planet_mut(home1)
+            .unwrap()
+            .add_materials(100.0);
+        let humans = game.get_race_mut(race1).unwrap();
+        let scout = humans
+            .add_ship_type("Scout".to_string(), ShipDesign::new(1.0, 0, 0.0, 0.0, 0.0))
+            .unwrap();
+        let freighter = humans
+            .add_ship_type(
+                "Freighter".to_string(),
+                ShipDesign::new(1.0, 0, 0.0, 0.0, 1.0),
+            )
+            .unwrap();
+        let first_scout = game.build_ship_of_type(home1, scout).unwrap();
+        let freighter_ship = game.build_ship_of_type(home1, freighter).unwrap();
+        let second_scout = game.build_ship_of_type(home1, scout).unwrap();
+        game.galaxy_mut()
+            .get_planet_mut(home1)
+            .unwrap()
+            .set_production_type(ProductionType::Ships(freighter));
+
+        let report = game.race_report(race1).unwrap();
+
+        let ships: Vec<(ShipId, Option<&str>)> = report
+            .ships
+            .iter()
+            .map(|s| (s.id, s.ship_type.as_deref()))
+            .collect();
+        assert_eq!(
+            ships,
+            vec![
+                (freighter_ship, Some("Freighter")),
+                (first_scout, Some("Scout")),
+                (second_scout, Some("Scout")),
+            ]
+        );
+        assert_eq!(report.ship_types.len(), 2);
+        game.set_route(race1, home1, Some(CargoType::Materials), Some(PlanetId(1)))
+            .unwrap();
+        let report = game.race_report(race1).unwrap();
+        let text = report.to_string();
+        assert!(text.contains("Your Ship Types"));
+        assert!(text.contains("Your Routes"));
+        assert!(text.contains("Freighter"));
+        assert_eq!(
+            production_name(report.planets[0].production, &report.ship_types),
+            "Freighter"
+        );
+        let group = game
+            .merge_ships(race1, &[first_scout, second_scout])
+            .unwrap();
+        game.join_fleet(race1, group, "Pickets").unwrap();
+        let report = game.race_report(race1).unwrap();
+        let scout_entry = report.ships.iter().find(|s| s.id == second_scout).unwrap();
+        assert_eq!(scout_entry.group, Some(group));
+        assert_eq!(scout_entry.fleet.as_deref(), Some("Pickets"));
+        assert_eq!(report.fleets.len(), 1);
+        assert_eq!(report.fleets[0].ships, 2);
+        assert_eq!(
+            report.fleets[0].location,
+            Some(ShipLocation::AtPlanet(home1))
+        );
+        assert!(report.to_string().contains("Your Fleets"));
+    }
+
+    #[test]
+    fn test_unknown_race_has_no_report() {
+        let (game, ..) = two_race_game();
+        assert!(game.race_report(RaceId(5)).is_none());
+    }
+}
@@ -0,0 +1,299 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::error::GameError;
+use crate::game_state::GameState;
+use crate::planet::PlanetId;
+use crate::race::RaceId;
+use crate::ship::CargoType;
+use crate::ship::ShipId;
+use crate::ship::ShipLocation;
+
+/// A standing order to ship cargo from one planet to another every turn, as
+/// GalaxyNG routes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Route {
+    pub origin: PlanetId,
+    pub destination: PlanetId,
+    /// What to carry; `None` sends empty ships
+    pub cargo: Option<CargoType>,
+}
+
+impl Route {
+    /// GalaxyNG fills ships in this order: colonists, capital, materials and
+    /// finally empty ships
+    fn priority(&self) -> u8 {
+        match self.cargo {
+            Some(CargoType::Colonists) => 0,
+            Some(CargoType::Capital) => 1,
+            Some(CargoType::Materials) => 2,
+            None => 3,
+        }
+    }
+}
+
+impl GameState {
+    /// Set (or with no destination, cancel) a race's route for one kind of
+    /// cargo leaving one of its planets
+    pub fn set_route(
+        &mut self,
+        race_id: RaceId,
+        origin: PlanetId,
+        cargo: Option<CargoType>,
+        destination: Option<PlanetId>,
+    ) -> Result<(), GameError> {
+        let planet = self
+            .galaxy()
+            .get_planet(origin)
+            .ok_or(GameError::UnknownPlanet(origin))?;
+        if planet.owner() != Some(race_id.0) {
+            return Err(GameError::NotPlanetOwner {
+                planet: origin,
+                race: race_id,
+            });
+        }
+        if let Some(destination) = destination {
+            if destination == origin {
+                return Err(GameError::RouteToOrigin(origin));
+            }
+            if self.galaxy().get_planet(destination).is_none() {
+                return Err(GameError::UnknownPlanet(destination));
+            }
+        }
+
+        let race = self
+            .get_race_mut(race_id)
+            .ok_or(GameError::UnknownRace(race_id))?;
+        match destination {
+            Some(destination) => race.set_route(Route {
+                origin,
+                destination,
+                cargo,
+            }),
+            None => race.clear_route(origin, cargo),
+        }
+        Ok(())
+    }
+
+    /// Load idle cargo ships waiting at route origins and send them off
+    ///
+    /// Only ships with cargo space and an empty hold follow routes. Routes
+    /// from planets the race no longer owns are dropped.
+    pub(crate) fn dispatch_routes(&mut self) {
+        let (mut routes, lost): (Vec<_>, Vec<_>) = self
+            .races()
+            .flat_map(|race| race.routes().iter().map(|route| (race.id(), *route)))
+            .partition(|(race_id, route)| {
+                self.galaxy()
+                    .get_planet(route.origin)
+                    .is_some_and(|planet| planet.owner() == Some(race_id.0))
+            });
+        for (race_id, route) in lost {
+            if let Some(race) = self.get_race_mut(race_id) {
+                race.clear_route(route.origin, route.cargo);
+            }
+        }
+
+        routes.sort_by_key(|(_, route)| route.priority());
+
+        for (race_id, route) in routes {
+            let idle: Vec<ShipId> = self
+                .ships()
+                .filter(|ship| {
+                    ship.owner() == race_id
+                        && *ship.location() == ShipLocation::AtPlanet(route.origin)
+                        && ship.design().cargo_mass() > 0.0
+                        && ship.total_cargo() == 0.0
+                })
+                .map(|ship| ship.id())
+                .collect();
+
+            for ship_id in idle {
+                // Stop once the origin runs out of this cargo
+                if let Some(cargo) = route.cargo
+                    && self.load_ship(ship_id, cargo, None).is_err()
+                {
+                    break;
+                }
+                // The ship is docked and both planets exist, so this cannot fail
+                if self.order_ship_travel(ship_id, route.destination).is_ok()
+                    && let Some(ship) = self.get_ship_mut(ship_id)
+                {
+                    ship.set_route(Some(route));
+                }
+            }
+        }
+    }
+
+    /// Unload the cargo of ships that reached the destination of the route
+    /// they were sent along
+    ///
+    /// Only ships sent by [`Self::dispatch_routes`] are unloaded, and only once
+    /// they dock; ships that docked elsewhere (turned back or sent on by hand)
+    /// are no longer on the route. Cargo that cannot be unloaded (colonists at
+    /// a planet that has fallen to another race) stays on board.
+    pub(crate) fn deliver_routed_cargo(&mut self) {
+        let docked: Vec<(ShipId, Route, bool)> = self
+            .ships()
+            .filter_map(|ship| {
+                let route = ship.route()?;
+                let planet = ship.location().planet_id()?;
+                Some((ship.id(), route, planet == route.destination))
+            })
+            .collect();
+
+        for (ship_id, route, arrived) in docked {
+            if arrived && let Some(cargo) = route.cargo {
+                let _ = self.unload_ship(ship_id, cargo, None);
+            }
+            if let Some(ship) = self.get_ship_mut(ship_id) {
+                ship.set_route(None);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::planet::Position;
+    use crate::ship::ShipDesign;
+
+    fn game_with_route_planets() -> (GameState, RaceId, PlanetId, PlanetId) {
+        let mut game = GameState::new(1000.0, 1000.0);
+        let home = game
+            .galaxy_mut()
+            .add_planet(Position::new(100.0, 100.0), 100, Some(0));
+        let target = game
+            .galaxy_mut()
+            .add_planet(Position::new(105.0, 100.0), 50, None);
+        let race = game.add_race("Humans".to_string(), home.0);
+        game.galaxy_mut()
+            .get_planet_mut(home)
+            .unwrap()
+            .add_materials(100.0);
+        (game, race, home, target)
+    }
+
+    #[test]
+    fn test_set_route_errors() {
+        let (mut game, race, home, target) = game_with_route_planets();
+
+        assert_eq!(
+            game.set_route(race, target, None, Some(home)),
+            Err(GameError::NotPlanetOwner {
+                planet: target,
+                race,
+            })
+        );
+        assert_eq!(
+            game.set_route(race, home, None, Some(home)),
+            Err(GameError::RouteToOrigin(home))
+        );
+        assert_eq!(
+            game.set_route(race, home, None, Some(PlanetId(9))),
+            Err(GameError::UnknownPlanet(PlanetId(9)))
+        );
+
+        game.set_route(race, home, Some(CargoType::Materials), Some(target))
+            .unwrap();
+        game.set_route(race, home, Some(CargoType::Colonists), Some(target))
+            .unwrap();
+        game.set_route(race, home, Some(CargoType::Materials), None)
+            .unwrap();
+        assert_eq!(
+            game.get_race(race).unwrap().routes(),
+            &[Route {
+                origin: home,
+                destination: target,
+                cargo: Some(CargoType::Colonists),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_routes_end_when_origin_is_lost() {
+        let (mut game, race, home, target) = game_with_route_planets();
+        let hauler = game
+            .build_ship(home, ShipDesign::new(5.0, 0, 0.0, 0.0, 5.0))
+            .unwrap();
+        game.set_route(race, home, None, Some(target)).unwrap();
+        game.galaxy_mut()
+            .get_planet_mut(home)
+            .unwrap()
+            .set_owner(None);
+
+        game.advance_turn();
+
+        assert!(game.get_race(race).unwrap().routes().is_empty());
+        assert_eq!(
+            game.get_ship(hauler).unwrap().location(),
+            &ShipLocation::AtPlanet(home)
+        );
+    }
+
+    #[test]
+    fn test_route_carries_colonists_every_turn() {
+        let (mut game, race, home, target) = game_with_route_planets();
+        game.galaxy_mut()
+            .get_planet_mut(home)
+            .unwrap()
+            .add_colonists(1.0);
+        let hauler = game
+            .build_ship(home, ShipDesign::new(5.0, 0, 0.0, 0.0, 5.0))
+            .unwrap();
+        let warship = game
+            .build_ship(home, ShipDesign::new(5.0, 1, 1.0, 0.0, 0.0))
+            .unwrap();
+        game.set_route(race, home, Some(CargoType::Colonists), Some(target))
+            .unwrap();
+        game.set_route(race, target, None, Some(home)).unwrap_err();
+
+        // Loaded and sent out, then landed at the (unowned) destination
+        game.advance_turn();
+        let target_planet = game.galaxy().get_planet(target).unwrap();
+        assert_eq!(target_planet.owner(), Some(race.0));
+        // 8 population landed, then grew by 8%
+        assert!((target_planet.population() - 8.64).abs() < 1e-9);
+        assert_eq!(game.get_ship(hauler).unwrap().total_cargo(), 0.0);
+        // Ships without cargo space ignore routes
+        assert_eq!(
+            game.get_ship(warship).unwrap().location(),
+            &ShipLocation::AtPlanet(home)
+        );
+
+        // Cargo loaded by hand is left alone at a route destination
+        let parked = game
+            .build_ship(home, ShipDesign::new(5.0, 0, 0.0, 0.0, 5.0))
+            .unwrap();
+        game.load_ship(parked, CargoType::Materials, Some(2.0))
+            .unwrap();
+        game.order_ship_travel(parked, target).unwrap();
+        game.advance_turn();
+        assert_eq!(
+            game.get_ship(parked).unwrap().location(),
+            &ShipLocation::AtPlanet(target)
+        );
+        assert_eq!(
+            game.get_ship(parked).unwrap().cargo(CargoType::Materials),
+            2.0
+        );
+        game.set_route(race, home, Some(CargoType::Materials), Some(target))
+            .unwrap();
+        game.advance_turn();
+        assert_eq!(
+            game.get_ship(parked).unwrap().cargo(CargoType::Materials),
+            2.0
+        );
+        game.set_route(race, home, Some(CargoType::Materials), None)
+            .unwrap();
+
+        // The new colony can send the empty ship back for the next load
+        game.set_route(race, target, None, Some(home)).unwrap();
+        game.advance_turn();
+        assert_eq!(
+            game.get_ship(hauler).unwrap().location(),
+            &ShipLocation::AtPlanet(home)
+        );
+    }
+}
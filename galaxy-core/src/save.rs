@@ -0,0 +1,281 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::game_state::GameState;
+
+/// Current version of the on-disk save format
+///
+/// Bump this whenever a change to the game state makes older saves unreadable.
+pub const SAVE_FORMAT_VERSION: u32 = 14;
+
+/// Magic bytes at the start of every binary save
+const BINARY_MAGIC: &[u8; 4] = b"GXSV";
+
+/// On-disk encoding of a saved game
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveFormat {
+    /// Human-readable JSON, handy for sharing and debugging
+    Json,
+    /// Compact binary encoding
+    Binary,
+}
+
+impl SaveFormat {
+    /// Choose a format from a file name: `.json` is JSON, anything else is
+    /// binary
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Self::Json,
+            _ => Self::Binary,
+        }
+    }
+}
+
+/// Errors raised while saving or loading a game
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Binary(bincode::Error),
+    /// The save was written by an incompatible version of the game
+    UnsupportedVersion(u32),
+    /// The data is neither a JSON nor a binary save
+    UnknownFormat,
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "I/O error: {err}"),
+            Self::Json(err) => write!(f, "invalid JSON save: {err}"),
+            Self::Binary(err) => write!(f, "invalid binary save: {err}"),
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported save version {version} (expected {SAVE_FORMAT_VERSION})"
+            ),
+            Self::UnknownFormat => write!(f, "unrecognized save format"),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Json(err) => Some(err),
+            Self::Binary(err) => Some(err),
+            Self::UnsupportedVersion(_) | Self::UnknownFormat => None,
+        }
+    }
+}
+
+impl From<io::Error> for SaveError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+impl From<bincode::Error> for SaveError {
+    fn from(err: bincode::Error) -> Self {
+        Self::Binary(err)
+    }
+}
+
+/// JSON save layout: the format version next to the game itself
+#[derive(Serialize)]
+struct JsonSave<'a> {
+    version: u32,
+    game: &'a GameState,
+}
+
+/// Only the version, so it can be checked before decoding the game
+#[derive(Deserialize)]
+struct JsonSaveHeader {
+    version: u32,
+}
+
+#[derive(Deserialize)]
+struct JsonSaveOwned {
+    game: GameState,
+}
+
+impl GameState {
+    /// Save the complete game state to a file
+    pub fn save(&self, path: impl AsRef<Path>, format: SaveFormat) -> Result<(), SaveError> {
+        fs::write(path, self.to_bytes(format)?)?;
+        Ok(())
+    }
+
+    /// Load a game previously written by [`GameState::save`]
+    ///
+    /// The format is detected from the file contents.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SaveError> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    /// Encode the complete game state
+    pub fn to_bytes(&self, format: SaveFormat) -> Result<Vec<u8>, SaveError> {
+        match format {
+            SaveFormat::Json => {
+                let save = JsonSave {
+                    version: SAVE_FORMAT_VERSION,
+                    game: self,
+                };
+                Ok(serde_json::to_vec_pretty(&save)?)
+            }
+            SaveFormat::Binary => {
+                let mut bytes = BINARY_MAGIC.to_vec();
+                bytes.extend_from_slice(&SAVE_FORMAT_VERSION.to_le_bytes());
+                bincode::serialize_into(&mut bytes, self)?;
+                Ok(bytes)
+            }
+        }
+    }
+
+    /// Decode a game state encoded by [`GameState::to_bytes`]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SaveError> {
+        if let Some(rest) = bytes.strip_prefix(BINARY_MAGIC) {
+            let (version, payload) = rest.split_at_checked(4).ok_or(SaveError::UnknownFormat)?;
+            let version = u32::from_le_bytes(version.try_into().expect("4 bytes"));
+            if version != SAVE_FORMAT_VERSION {
+                return Err(SaveError::UnsupportedVersion(version));
+            }
+            return Ok(bincode::deserialize(payload)?);
+        }
+
+        if bytes.trim_ascii_start().starts_with(b"{") {
+            let header: JsonSaveHeader = serde_json::from_slice(bytes)?;
+            if header.version != SAVE_FORMAT_VERSION {
+                return Err(SaveError::UnsupportedVersion(header.version));
+            }
+            let save: JsonSaveOwned = serde_json::from_slice(bytes)?;
+            return Ok(save.game);
+        }
+
+        Err(SaveError::UnknownFormat)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init::GameConfig;
+    use crate::init::initialize_game;
+    use crate::race::RaceId;
+    use crate::race::TechnologyType;
+
+    /// Canonical snapshot of a game; `Debug` is unsuitable because the RNG
+    /// prints its internal buffer position
+    fn snapshot(game: &GameState) -> Vec<u8> {
+        game.to_bytes(SaveFormat::Json).unwrap()
+    }
+
+    fn sample_game() -> GameState {
+        let mut game = initialize_game(GameConfig {
+            galaxy_width: 500.0,
+            galaxy_height: 500.0,
+            num_races: 3,
+            num_planets: 10,
+            seed: 77,
+        });
+        game.diplomacy_mut().make_hostile(RaceId(0), RaceId(1));
+        // Partial research progress lives in private TechProgress
+        game.get_race_mut(RaceId(2))
+            .unwrap()
+            .add_research(TechnologyType::Drive, 10.0);
+        for _ in 0..5 {
+            game.advance_turn();
+        }
+        game
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let game = sample_game();
+        let bytes = game.to_bytes(SaveFormat::Json).unwrap();
+        let loaded = GameState::from_bytes(&bytes).unwrap();
+
+        assert_eq!(snapshot(&loaded), bytes);
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let game = sample_game();
+        let bytes = game.to_bytes(SaveFormat::Binary).unwrap();
+        let loaded = GameState::from_bytes(&bytes).unwrap();
+
+        assert_eq!(snapshot(&loaded), snapshot(&game));
+        // Binary should be the compact one
+        assert!(bytes.len() < game.to_bytes(SaveFormat::Json).unwrap().len());
+    }
+
+    #[test]
+    fn test_loaded_game_continues_identically() {
+        let mut original = sample_game();
+        let mut resumed =
+            GameState::from_bytes(&original.to_bytes(SaveFormat::Binary).unwrap()).unwrap();
+
+        // Id counters and the random stream must carry over
+        for _ in 0..10 {
+            original.advance_turn();
+            resumed.advance_turn();
+        }
+
+        assert_eq!(snapshot(&resumed), snapshot(&original));
+    }
+
+    #[test]
+    fn test_save_and_load_file() {
+        let game = sample_game();
+        let path = std::env::temp_dir().join(format!("galaxy-save-{}.json", std::process::id()));
+
+        game.save(&path, SaveFormat::from_path(&path)).unwrap();
+        let loaded = GameState::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(snapshot(&loaded.unwrap()), snapshot(&game));
+    }
+
+    #[test]
+    fn test_rejects_other_version() {
+        let game = sample_game();
+        let mut bytes = game.to_bytes(SaveFormat::Binary).unwrap();
+        bytes[4..8].copy_from_slice(&(SAVE_FORMAT_VERSION + 1).to_le_bytes());
+
+        assert!(matches!(
+            GameState::from_bytes(&bytes),
+            Err(SaveError::UnsupportedVersion(v)) if v == SAVE_FORMAT_VERSION + 1
+        ));
+
+        let json = br#"{"version": 999, "game": {}}"#;
+        assert!(matches!(
+            GameState::from_bytes(json),
+            Err(SaveError::UnsupportedVersion(999))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_garbage() {
+        assert!(matches!(
+            GameState::from_bytes(b"not a save"),
+            Err(SaveError::UnknownFormat)
+        ));
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(SaveFormat::from_path("game.json"), SaveFormat::Json);
+        assert_eq!(SaveFormat::from_path("game.sav"), SaveFormat::Binary);
+    }
+}
@@ -1,12 +1,21 @@
 use std::fmt;
 
-use bevy::prelude::*;
+#[cfg(feature = "bevy")]
+use bevy::prelude::Component;
+use serde::Deserialize;
+use serde::Serialize;
 
+use crate::error::GameError;
 use crate::planet::PlanetId;
+use crate::planet::SHIP_PRODUCTION_PER_MASS;
+use crate::planet::ShipTypeId;
 use crate::race::RaceId;
+use crate::race::Technology;
+use crate::route::Route;
 
 /// Unique identifier for a ship
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Component)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(Component))]
 pub struct ShipId(pub u32);
 
 impl fmt::Display for ShipId {
@@ -15,8 +24,47 @@ impl fmt::Display for ShipId {
     }
 }
 
+/// A part of a ship design
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShipComponent {
+    Drive,
+    Weapons,
+    Shields,
+    Cargo,
+}
+
+/// Why a ship design breaks the GalaxyNG design rules
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum DesignError {
+    /// Masses must be 0 or at least 1 (and finite)
+    InvalidMass {
+        component: ShipComponent,
+        mass: f64,
+    },
+    AttacksWithoutWeapons,
+    WeaponsWithoutAttacks,
+    /// All components have zero mass
+    Empty,
+}
+
+impl fmt::Display for DesignError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidMass { component, mass } => {
+                write!(f, "{component:?} mass {mass} must be 0 or at least 1")
+            }
+            Self::AttacksWithoutWeapons => write!(f, "attacks need a weapons mass"),
+            Self::WeaponsWithoutAttacks => write!(f, "weapons need at least one attack"),
+            Self::Empty => write!(f, "a ship needs at least one component"),
+        }
+    }
+}
+
+impl std::error::Error for DesignError {}
+
 /// Ship design specification (GalaxyNG format)
-#[derive(Debug, Clone, Copy, PartialEq, Component)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(Component))]
 pub struct ShipDesign {
     drive_mass: f64,
     attacks: u32,
@@ -26,6 +74,7 @@ pub struct ShipDesign {
 }
 
 impl ShipDesign {
+    /// Create a design without checking it; see [`Self::try_new`]
     pub fn new(
         drive_mass: f64,
         attacks: u32,
@@ -42,6 +91,45 @@ impl ShipDesign {
         }
     }
 
+    /// Create a design that follows the GalaxyNG design rules
+    pub fn try_new(
+        drive_mass: f64,
+        attacks: u32,
+        weapons_mass: f64,
+        shields_mass: f64,
+        cargo_mass: f64,
+    ) -> Result<Self, DesignError> {
+        let design = Self::new(drive_mass, attacks, weapons_mass, shields_mass, cargo_mass);
+        design.validate()?;
+        Ok(design)
+    }
+
+    /// Check the GalaxyNG design rules
+    ///
+    /// Every component mass is either 0 or at least 1, a ship with weapons
+    /// needs at least one attack (and vice versa), and a design cannot be
+    /// empty.
+    pub fn validate(&self) -> Result<(), DesignError> {
+        let components = [
+            (ShipComponent::Drive, self.drive_mass),
+            (ShipComponent::Weapons, self.weapons_mass),
+            (ShipComponent::Shields, self.shields_mass),
+            (ShipComponent::Cargo, self.cargo_mass),
+        ];
+        for (component, mass) in components {
+            if !(mass == 0.0 || mass >= 1.0) || !mass.is_finite() {
+                return Err(DesignError::InvalidMass { component, mass });
+            }
+        }
+
+        match (self.attacks, self.weapons_mass > 0.0) {
+            (0, true) => Err(DesignError::WeaponsWithoutAttacks),
+            (1.., false) => Err(DesignError::AttacksWithoutWeapons),
+            _ if self.ship_mass() == 0.0 => Err(DesignError::Empty),
+            _ => Ok(()),
+        }
+    }
+
     pub fn drive_mass(&self) -> f64 {
         self.drive_mass
     }
@@ -113,7 +201,7 @@ impl ShipDesign {
 }
 
 /// Cargo types that ships can carry
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CargoType {
     Colonists,
     Materials,
@@ -121,11 +209,21 @@ pub enum CargoType {
 }
 
 /// A spaceship
-#[derive(Debug, Clone, Component)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(Component))]
 pub struct Ship {
     id: ShipId,
     owner: RaceId,
     design: ShipDesign,
+    /// The owner's ship type this ship was built as, if any
+    #[serde(default)]
+    ship_type: Option<ShipTypeId>,
+    /// Tech levels the ship was built (or last upgraded) with
+    #[serde(default)]
+    tech: Technology,
+    /// Route the ship was sent along and has yet to deliver for
+    #[serde(default)]
+    route: Option<Route>,
     current_hull: f64,
     location: ShipLocation,
     cargo_colonists: f64,
@@ -140,6 +238,9 @@ impl Ship {
             owner,
             current_hull: design.shields_mass(), // Hull = shields mass
             design,
+            ship_type: None,
+            tech: Technology::new(),
+            route: None,
             location: ShipLocation::AtPlanet(location),
             cargo_colonists: 0.0,
             cargo_materials: 0.0,
@@ -159,6 +260,74 @@ impl Ship {
         &self.design
     }
 
+    /// Mark the ship as built from one of its owner's ship types
+    pub fn with_ship_type(mut self, ship_type: ShipTypeId) -> Self {
+        self.ship_type = Some(ship_type);
+        self
+    }
+
+    pub fn ship_type(&self) -> Option<ShipTypeId> {
+        self.ship_type
+    }
+
+    pub fn route(&self) -> Option<Route> {
+        self.route
+    }
+
+    pub(crate) fn set_route(&mut self, route: Option<Route>) {
+        self.route = route;
+    }
+
+    /// Give the ship the tech levels of its builder
+    pub fn with_tech(mut self, tech: Technology) -> Self {
+        self.tech = tech;
+        self
+    }
+
+    pub fn tech(&self) -> &Technology {
+        &self.tech
+    }
+
+    /// Production needed to bring the ship up to the given tech levels
+    ///
+    /// GalaxyNG cost: 10 × mass × (1 − ship tech / new tech), summed over the
+    /// drive, weapons, shields and cargo parts of the ship.
+    pub fn upgrade_cost(&self, tech: &Technology) -> f64 {
+        let design = &self.design;
+        let weapons =
+            design.ship_mass() - design.drive_mass() - design.shields_mass() - design.cargo_mass();
+        let parts = [
+            (
+                design.drive_mass(),
+                self.tech.drive_level(),
+                tech.drive_level(),
+            ),
+            (weapons, self.tech.weapon_level(), tech.weapon_level()),
+            (
+                design.shields_mass(),
+                self.tech.shield_level(),
+                tech.shield_level(),
+            ),
+            (
+                design.cargo_mass(),
+                self.tech.cargo_level(),
+                tech.cargo_level(),
+            ),
+        ];
+        parts
+            .into_iter()
+            .filter(|(_, current, new)| new > current)
+            .map(|(mass, current, new)| {
+                SHIP_PRODUCTION_PER_MASS * mass * (1.0 - current as f64 / new as f64)
+            })
+            .sum()
+    }
+
+    /// Refit the ship with new tech levels
+    pub fn upgrade(&mut self, tech: Technology) {
+        self.tech = tech;
+    }
+
     pub fn current_hull(&self) -> f64 {
         self.current_hull
     }
@@ -193,8 +362,21 @@ impl Ship {
     }
 
     /// Load cargo onto ship (returns amount actually loaded)
-    pub fn load_cargo(&mut self, cargo_type: CargoType, amount: f64, cargo_tech: f64) -> f64 {
+    ///
+    /// Loads as much of the amount as fits; fails if nothing fits at all.
+    pub fn load_cargo(
+        &mut self,
+        cargo_type: CargoType,
+        amount: f64,
+        cargo_tech: f64,
+    ) -> Result<f64, GameError> {
+        if amount.is_nan() || amount <= 0.0 {
+            return Err(GameError::InvalidAmount(amount));
+        }
         let available = self.available_cargo(cargo_tech);
+        if available <= 0.0 {
+            return Err(GameError::CargoHoldFull(self.id));
+        }
         let to_load = amount.min(available);
 
         match cargo_type {
@@ -203,7 +385,7 @@ impl Ship {
             CargoType::Capital => self.cargo_capital += to_load,
         }
 
-        to_load
+        Ok(to_load)
     }
 
     /// Unload cargo from ship (returns amount actually unloaded)
@@ -236,24 +418,36 @@ impl Ship {
         }
     }
 
-    /// Calculate travel speed based on design, technology, and cargo
-    pub fn travel_speed(&self, drive_tech: f64) -> f64 {
-        self.design.speed(drive_tech, self.total_cargo())
+    /// Calculate travel speed based on design, the ship's drive tech, and
+    /// cargo
+    pub fn travel_speed(&self) -> f64 {
+        self.design
+            .speed(self.tech.drive_level() as f64, self.load_mass())
     }
 
-    /// Calculate attack strength with technology
-    pub fn attack_strength(&self, weapons_tech: f64) -> f64 {
-        self.design.attack_strength(weapons_tech)
+    /// Calculate attack strength with the ship's weapons tech
+    pub fn attack_strength(&self) -> f64 {
+        self.design.attack_strength(self.tech.weapon_level() as f64)
+    }
+
+    /// Calculate defence strength with the ship's shields tech
+    pub fn defence_strength(&self) -> f64 {
+        self.design
+            .defence_strength(self.tech.shield_level() as f64, self.load_mass())
     }
 
-    /// Calculate defence strength with technology
-    pub fn defence_strength(&self, shields_tech: f64) -> f64 {
-        self.design.defence_strength(shields_tech, 0.0) // No cargo for now
+    /// Mass the cargo on board adds to the ship
+    ///
+    /// As in GalaxyNG, better cargo tech makes the same load weigh less:
+    /// load / cargo tech.
+    pub fn load_mass(&self) -> f64 {
+        self.total_cargo() / self.tech.cargo_level().max(1) as f64
     }
 }
 
 /// Ship location - either at a planet or traveling
-#[derive(Debug, Clone, Copy, PartialEq, Component)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(Component))]
 pub enum ShipLocation {
     AtPlanet(PlanetId),
     Traveling {
@@ -281,6 +475,50 @@ impl ShipLocation {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::race::TechnologyType;
+
+    #[test]
+    fn test_design_validation() {
+        assert!(ShipDesign::try_new(2.48, 1, 1.20, 1.27, 0.0).is_ok());
+        assert!(ShipDesign::try_new(1.0, 0, 0.0, 0.0, 0.0).is_ok());
+        // Immobile defence platforms are allowed
+        assert!(ShipDesign::try_new(0.0, 1, 1.0, 5.0, 0.0).is_ok());
+
+        assert_eq!(
+            ShipDesign::try_new(0.5, 0, 0.0, 1.0, 0.0),
+            Err(DesignError::InvalidMass {
+                component: ShipComponent::Drive,
+                mass: 0.5,
+            })
+        );
+        assert_eq!(
+            ShipDesign::try_new(1.0, 0, 0.0, -2.0, 0.0),
+            Err(DesignError::InvalidMass {
+                component: ShipComponent::Shields,
+                mass: -2.0,
+            })
+        );
+        assert!(matches!(
+            ShipDesign::try_new(1.0, 0, 0.0, 0.0, f64::NAN),
+            Err(DesignError::InvalidMass {
+                component: ShipComponent::Cargo,
+                ..
+            })
+        ));
+        assert!(ShipDesign::try_new(1.0, 0, 0.0, f64::INFINITY, 0.0).is_err());
+        assert_eq!(
+            ShipDesign::try_new(1.0, 2, 0.0, 1.0, 0.0),
+            Err(DesignError::AttacksWithoutWeapons)
+        );
+        assert_eq!(
+            ShipDesign::try_new(1.0, 0, 3.0, 1.0, 0.0),
+            Err(DesignError::WeaponsWithoutAttacks)
+        );
+        assert_eq!(
+            ShipDesign::try_new(0.0, 0, 0.0, 0.0, 0.0),
+            Err(DesignError::Empty)
+        );
+    }
 
     #[test]
     fn test_ship_mass_calculation() {
@@ -339,11 +577,47 @@ mod tests {
         let defence = battleship.defence_strength(2.0, 0.0);
         assert!(defence > 0.0);
     }
+
+    #[test]
+    fn test_ship_uses_its_own_tech() {
+        let design = ShipDesign::new(10.0, 1, 10.0, 10.0, 0.0);
+        let old = Ship::new(ShipId(1), RaceId(0), design, PlanetId(0));
+        let mut tech = Technology::new();
+        tech.advance(TechnologyType::Drive);
+        tech.advance(TechnologyType::Weapon);
+        let new = Ship::new(ShipId(2), RaceId(0), design, PlanetId(0)).with_tech(tech);
+
+        assert_eq!(new.travel_speed(), 2.0 * old.travel_speed());
+        assert_eq!(new.attack_strength(), 2.0 * old.attack_strength());
+        assert_eq!(new.defence_strength(), old.defence_strength());
+    }
+
+    #[test]
+    fn test_upgrade_cost() {
+        let design = ShipDesign::new(10.0, 2, 4.0, 6.0, 0.0);
+        let mut ship = Ship::new(ShipId(1), RaceId(0), design, PlanetId(0));
+        let mut tech = Technology::new();
+        tech.advance(TechnologyType::Drive);
+        tech.advance(TechnologyType::Weapon);
+        tech.advance(TechnologyType::Weapon);
+        tech.advance(TechnologyType::Cargo);
+
+        // Drive: 10 × 10 × (1 − 1/2) = 50; weapons (4 + 4/2 = 6 mass):
+        // 10 × 6 × (1 − 1/3) = 40; shields are unchanged, no cargo mass
+        assert!((ship.upgrade_cost(&tech) - 90.0).abs() < 1e-9);
+
+        ship.upgrade(tech.clone());
+        assert_eq!(ship.tech(), &tech);
+        assert_eq!(ship.upgrade_cost(&tech), 0.0);
+        // Older tech is never a downgrade worth paying for
+        assert_eq!(ship.upgrade_cost(&Technology::new()), 0.0);
+    }
 }
 
 #[cfg(test)]
 mod cargo_tests {
     use super::*;
+    use crate::race::TechnologyType;
 
     #[test]
     fn test_cargo_capacity_with_tech() {
@@ -369,21 +643,31 @@ mod cargo_tests {
         assert_eq!(ship.available_cargo(1.0), 7.5);
 
         // Load 3.0 colonists
-        let loaded = ship.load_cargo(CargoType::Colonists, 3.0, 1.0);
+        let loaded = ship.load_cargo(CargoType::Colonists, 3.0, 1.0).unwrap();
         assert_eq!(loaded, 3.0);
         assert_eq!(ship.cargo(CargoType::Colonists), 3.0);
         assert_eq!(ship.available_cargo(1.0), 4.5);
 
         // Load 2.0 materials
-        let loaded = ship.load_cargo(CargoType::Materials, 2.0, 1.0);
+        let loaded = ship.load_cargo(CargoType::Materials, 2.0, 1.0).unwrap();
         assert_eq!(loaded, 2.0);
         assert_eq!(ship.total_cargo(), 5.0);
 
         // Try to load more than available (2.5 available, try 5.0)
-        let loaded = ship.load_cargo(CargoType::Capital, 5.0, 1.0);
+        let loaded = ship.load_cargo(CargoType::Capital, 5.0, 1.0).unwrap();
         assert_eq!(loaded, 2.5);
         assert_eq!(ship.total_cargo(), 7.5);
         assert_eq!(ship.available_cargo(1.0), 0.0);
+
+        // Nothing fits any more
+        assert_eq!(
+            ship.load_cargo(CargoType::Capital, 1.0, 1.0),
+            Err(GameError::CargoHoldFull(ShipId(1)))
+        );
+        assert_eq!(
+            ship.load_cargo(CargoType::Capital, -1.0, 1.0),
+            Err(GameError::InvalidAmount(-1.0))
+        );
     }
 
     #[test]
@@ -392,7 +676,7 @@ mod cargo_tests {
         let mut ship = Ship::new(ShipId(1), RaceId(0), design, PlanetId(0));
 
         // Load cargo
-        ship.load_cargo(CargoType::Materials, 10.0, 1.0);
+        ship.load_cargo(CargoType::Materials, 10.0, 1.0).unwrap();
         assert_eq!(ship.cargo(CargoType::Materials), 10.0);
 
         // Unload 4.0
@@ -412,24 +696,44 @@ mod cargo_tests {
         let mut ship = Ship::new(ShipId(1), RaceId(0), design, PlanetId(0));
 
         // Empty ship speed
-        let empty_speed = ship.travel_speed(1.0);
+        let empty_speed = ship.travel_speed();
 
         // Load cargo
-        ship.load_cargo(CargoType::Materials, 5.0, 1.0);
+        ship.load_cargo(CargoType::Materials, 5.0, 1.0).unwrap();
 
         // Loaded ship should be slower
-        let loaded_speed = ship.travel_speed(1.0);
+        let loaded_speed = ship.travel_speed();
         assert!(loaded_speed < empty_speed);
     }
 
+    #[test]
+    fn test_cargo_lowers_defence() {
+        let design = ShipDesign::new(10.0, 0, 0.0, 10.0, 10.0);
+        let mut ship = Ship::new(ShipId(1), RaceId(0), design, PlanetId(0));
+        let empty = ship.defence_strength();
+
+        ship.load_cargo(CargoType::Materials, 10.0, 1.0).unwrap();
+        let loaded = ship.defence_strength();
+        assert!(loaded < empty);
+        assert_eq!(loaded, design.defence_strength(1.0, 10.0));
+
+        // Cargo tech 2 halves the mass of the same load
+        let mut tech = Technology::new();
+        tech.advance(TechnologyType::Cargo);
+        let ship = ship.with_tech(tech);
+        assert_eq!(ship.load_mass(), 5.0);
+        assert!(ship.defence_strength() > loaded);
+        assert!(ship.defence_strength() < empty);
+    }
+
     #[test]
     fn test_cargo_types_separate() {
         let design = ShipDesign::new(5.0, 0, 0.0, 5.0, 10.0);
         let mut ship = Ship::new(ShipId(1), RaceId(0), design, PlanetId(0));
 
-        ship.load_cargo(CargoType::Colonists, 3.0, 1.0);
-        ship.load_cargo(CargoType::Materials, 5.0, 1.0);
-        ship.load_cargo(CargoType::Capital, 2.0, 1.0);
+        ship.load_cargo(CargoType::Colonists, 3.0, 1.0).unwrap();
+        ship.load_cargo(CargoType::Materials, 5.0, 1.0).unwrap();
+        ship.load_cargo(CargoType::Capital, 2.0, 1.0).unwrap();
 
         assert_eq!(ship.cargo(CargoType::Colonists), 3.0);
         assert_eq!(ship.cargo(CargoType::Materials), 5.0);
@@ -450,7 +754,7 @@ mod cargo_tests {
         assert_eq!(ship.available_cargo(2.0), 40.0);
 
         // Load 25.0 at tech 2.0 (should fit)
-        let loaded = ship.load_cargo(CargoType::Materials, 25.0, 2.0);
+        let loaded = ship.load_cargo(CargoType::Materials, 25.0, 2.0).unwrap();
         assert_eq!(loaded, 25.0);
 
         // But at tech 1.0, this would exceed capacity
@@ -1,7 +1,10 @@
 #![allow(unused_crate_dependencies)] // Test uses dependencies from main crate
 
 use galaxy_core::game_state::GameState;
+use galaxy_core::init::GameConfig;
+use galaxy_core::init::initialize_game;
 use galaxy_core::planet::Position;
+use galaxy_core::race::RaceId;
 use galaxy_core::racebot::Personality;
 
 #[test]
@@ -241,3 +244,32 @@ fn test_ai_race_identification() {
     assert!(game.get_race(ai_race).unwrap().is_ai_controlled());
     assert!(!game.get_race(human_race).unwrap().is_ai_controlled());
 }
+
+#[test]
+fn test_same_seed_replays_identical_game() {
+    let play = |seed| {
+        let mut game = initialize_game(GameConfig {
+            galaxy_width: 600.0,
+            galaxy_height: 600.0,
+            num_races: 4,
+            num_planets: 12,
+            seed,
+        });
+
+        // Everyone at war so combat (and its dice rolls) actually happens
+        for a in 0..4 {
+            for b in (a + 1)..4 {
+                game.diplomacy_mut().make_hostile(RaceId(a), RaceId(b));
+            }
+        }
+
+        for _turn in 0..40 {
+            game.advance_turn();
+        }
+
+        format!("{game:?}")
+    };
+
+    // Same seed and same (AI) orders must give a bit-identical game
+    assert_eq!(play(2024), play(2024));
+}
@@ -10,8 +10,10 @@ fn main() {
         galaxy_height: 1000.0,
         num_races: 4,
         num_planets: 15,
+        ..GameConfig::default()
     };
 
+    let seed = config.seed;
     let game = initialize_game(config);
 
     println!("=== GALAXY - Space Simulator ===");
@@ -21,6 +23,7 @@ fn main() {
         game.galaxy().width(),
         game.galaxy().height()
     );
+    println!("Seed: {}", seed);
     println!("Races: {}", game.races().count());
     println!("Planets: {}", game.galaxy().planets().count());
 